@@ -0,0 +1,149 @@
+//! Benchmarks for the borrowed variant of `ManagedMap`.
+//!
+//! These do not use the unstable `test` crate or any benchmarking dependency, so that they
+//! run on stable Rust with `cargo bench --features map`. Each case is run several times,
+//! and the median time per iteration is reported.
+
+extern crate managed;
+
+use std::mem;
+use std::ptr;
+use std::time::Instant;
+
+use managed::ManagedMap;
+use managed::util::search_packed;
+
+const CAPACITY: usize = 1024;
+const ITERATIONS: u32 = 1000;
+const RUNS: usize = 11;
+
+/// Returns `value`, preventing the optimizer from assuming anything about it.
+fn black_box<T>(value: T) -> T {
+    unsafe {
+        let result = ptr::read_volatile(&value);
+        mem::forget(value);
+        result
+    }
+}
+
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    let mut times = [0f64; RUNS];
+    for time in times.iter_mut() {
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            f()
+        }
+        *time = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    }
+    times.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+    println!("{:<48} {:>10.1} ns/iter", name, times[RUNS / 2]);
+}
+
+/// Clearing a large map holding only a few entries only overwrites their slots,
+/// rather than the whole backing slice.
+fn bench_clear_sparse() {
+    let mut pairs = [None; CAPACITY];
+    bench("clear, 3 of 1024 slots occupied", || {
+        let mut map = ManagedMap::from(black_box(&mut pairs[..]));
+        for key in 0..3u32 {
+            map.insert(key, key).unwrap();
+        }
+        map.clear();
+        black_box(&mut map);
+    });
+    bench("overwrite all slots, 3 of 1024 slots occupied", || {
+        {
+            let mut map = ManagedMap::from(black_box(&mut pairs[..]));
+            for key in 0..3u32 {
+                map.insert(key, key).unwrap();
+            }
+        }
+        for slot in pairs.iter_mut() {
+            *slot = None
+        }
+        black_box(&mut pairs);
+    });
+}
+
+/// Clearing a full map of `Copy` pairs is a fill of the occupied slots; compare it with
+/// a loop that checks each slot and stops at the first free one. Both refill the slice
+/// on every iteration.
+fn bench_clear_full() {
+    fn fill(pairs: &mut [Option<(u32, u32)>]) {
+        for (key, slot) in pairs.iter_mut().enumerate() {
+            *slot = Some((key as u32, key as u32))
+        }
+    }
+
+    let mut pairs = [None; CAPACITY];
+    bench("clear, 1024 of 1024 slots occupied", || {
+        fill(&mut pairs);
+        let mut map = ManagedMap::Borrowed(black_box(&mut pairs[..]), CAPACITY);
+        map.clear();
+        black_box(&mut map);
+    });
+    bench("overwrite until free slot, 1024 of 1024 occupied", || {
+        fill(&mut pairs);
+        for slot in black_box(&mut pairs[..]).iter_mut() {
+            if slot.is_none() {
+                break
+            }
+            *slot = None
+        }
+        black_box(&mut pairs);
+    });
+}
+
+/// Inserts a pair as an earlier version did, rotating the whole tail of the slice after
+/// the insertion point rather than only the entries in it.
+fn insert_rotating_tail(pairs: &mut [Option<(u32, u32)>], key: u32, value: u32) {
+    match search_packed(pairs, &key) {
+        Ok(idx) => pairs[idx] = Some((key, value)),
+        Err(idx) => {
+            let rotate_by = pairs.len() - idx - 1;
+            pairs[idx..].rotate_left(rotate_by);
+            pairs[idx] = Some((key, value))
+        }
+    }
+}
+
+/// Inserting 64 keys into a map with capacity for 1024 only shifts the entries with greater
+/// keys, so ascending keys shift nothing, and descending keys shift at most 63 entries.
+fn bench_insert() {
+    const COUNT: u32 = 64;
+    let mut pairs = [None; CAPACITY];
+    bench("insert 64 ascending keys", || {
+        let mut map = ManagedMap::from(black_box(&mut pairs[..]));
+        for key in 0..COUNT {
+            map.insert(key, key).unwrap();
+        }
+        map.clear();
+    });
+    bench("insert 64 ascending keys, rotating the tail", || {
+        let pairs = black_box(&mut pairs[..]);
+        for key in 0..COUNT {
+            insert_rotating_tail(pairs, key, key);
+        }
+        ManagedMap::from(pairs).clear();
+    });
+    bench("insert 64 descending keys", || {
+        let mut map = ManagedMap::from(black_box(&mut pairs[..]));
+        for key in (0..COUNT).rev() {
+            map.insert(key, key).unwrap();
+        }
+        map.clear();
+    });
+    bench("insert 64 descending keys, rotating the tail", || {
+        let pairs = black_box(&mut pairs[..]);
+        for key in (0..COUNT).rev() {
+            insert_rotating_tail(pairs, key, key);
+        }
+        ManagedMap::from(pairs).clear();
+    });
+}
+
+fn main() {
+    bench_clear_sparse();
+    bench_clear_full();
+    bench_insert();
+}
@@ -0,0 +1,166 @@
+use core::ops::{Deref, DerefMut};
+use core::fmt;
+
+use slice::ManagedSlice;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+#[cfg(all(feature = "serde", any(feature = "std", feature = "alloc")))]
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+
+/// A managed byte buffer.
+///
+/// This is a [ManagedSlice](enum.ManagedSlice.html) of bytes that, with the `serde` feature
+/// enabled, is serialized with `serialize_bytes` rather than as a sequence, which is much
+/// more compact in binary formats. Deserializing it copies the bytes into an owned buffer,
+/// and is only available with the `std` or `alloc` feature enabled.
+pub struct ManagedBytes<'a>(pub ManagedSlice<'a, u8>);
+
+impl<'a> ManagedBytes<'a> {
+    /// Returns the underlying managed slice.
+    pub fn into_inner(self) -> ManagedSlice<'a, u8> {
+        self.0
+    }
+}
+
+impl<'a> fmt::Debug for ManagedBytes<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'a> From<ManagedSlice<'a, u8>> for ManagedBytes<'a> {
+    fn from(value: ManagedSlice<'a, u8>) -> Self {
+        ManagedBytes(value)
+    }
+}
+
+impl<'a> From<&'a mut [u8]> for ManagedBytes<'a> {
+    fn from(value: &'a mut [u8]) -> Self {
+        ManagedBytes(ManagedSlice::Borrowed(value))
+    }
+}
+
+/// Copies the bytes into an owned buffer, as they cannot be borrowed mutably.
+///
+/// This is only available with the `std` or `alloc` feature enabled.
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, 'b> From<&'b [u8]> for ManagedBytes<'a> {
+    fn from(value: &'b [u8]) -> Self {
+        ManagedBytes(ManagedSlice::Owned(value.to_vec()))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> From<Vec<u8>> for ManagedBytes<'a> {
+    fn from(value: Vec<u8>) -> Self {
+        ManagedBytes(ManagedSlice::Owned(value))
+    }
+}
+
+impl<'a> Deref for ManagedBytes<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a> DerefMut for ManagedBytes<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> Serialize for ManagedBytes<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+#[cfg(all(feature = "serde", any(feature = "std", feature = "alloc")))]
+impl<'de, 'a> Deserialize<'de> for ManagedBytes<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a byte array")
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+                Ok(value.to_vec())
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(value)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte)
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor).map(ManagedBytes::from)
+    }
+}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::ManagedBytes;
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+
+    #[test]
+    fn test_borrowed() {
+        let mut data = [1u8, 2, 3];
+        let mut bytes = ManagedBytes::from(&mut data[..]);
+        bytes[0] = 10;
+        assert_eq!(&*bytes, &[10, 2, 3]);
+        assert_eq!(data, [10, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_owned() {
+        let bytes = ManagedBytes::from(&[1u8, 2, 3][..]);
+        assert_eq!(&*bytes, &[1, 2, 3]);
+        let bytes = ManagedBytes::from(Vec::from(&b"abc"[..]));
+        assert_eq!(&*bytes, b"abc");
+        assert_eq!(&*bytes.into_inner(), b"abc");
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "std"))]
+    fn test_serde_json() {
+        let mut data = [1u8, 2, 255];
+        let bytes = ManagedBytes::from(&mut data[..]);
+        let json = serde_json::to_string(&bytes).unwrap();
+        assert_eq!(json, "[1,2,255]");
+        let bytes: ManagedBytes = serde_json::from_str(&json).unwrap();
+        assert_eq!(&*bytes, &[1, 2, 255]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "std"))]
+    fn test_serde_postcard() {
+        let bytes = ManagedBytes::from(&[1u8, 2, 255][..]);
+        let encoded = postcard::to_allocvec(&bytes).unwrap();
+        // A length prefix followed by the bytes themselves.
+        assert_eq!(encoded, [3, 1, 2, 255]);
+        let bytes: ManagedBytes = postcard::from_bytes(&encoded).unwrap();
+        assert_eq!(&*bytes, &[1, 2, 255]);
+    }
+}
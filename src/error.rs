@@ -0,0 +1,52 @@
+use core::fmt;
+
+/// An error returned when there is no space left for a new element,
+/// holding the element that was rejected.
+///
+/// Fallible insertions return the element in this error rather than dropping it,
+/// so that it can be recovered with `into_inner` and e.g. inserted elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T>(T);
+
+impl<T> CapacityError<T> {
+    /// Creates an error holding the rejected element.
+    pub fn new(value: T) -> Self {
+        CapacityError(value)
+    }
+
+    /// Returns the rejected element.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "insufficient capacity")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for CapacityError<T> {}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::CapacityError;
+    #[cfg(feature = "std")]
+    use std::string::ToString;
+
+    #[test]
+    fn test_into_inner() {
+        let err = CapacityError::new(("a", 1));
+        assert_eq!(err.into_inner(), ("a", 1));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_display() {
+        assert_eq!(CapacityError::new(1).to_string(), "insufficient capacity");
+        let err: &dyn std::error::Error = &CapacityError::new(1);
+        assert!(err.source().is_none());
+    }
+}
@@ -7,15 +7,28 @@
 extern crate std;
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 extern crate alloc;
+#[cfg(feature = "serde")]
+extern crate serde;
 
+mod bytes;
+mod error;
 mod object;
+mod shared;
 mod slice;
 mod slotmap;
+pub mod util;
 #[cfg(feature = "map")]
 mod map;
+#[cfg(feature = "map")]
+mod map_by;
 
+pub use bytes::ManagedBytes;
+pub use error::CapacityError;
 pub use object::Managed;
-pub use slice::ManagedSlice;
+pub use shared::ManagedShared;
+pub use slice::{ManagedSlice,
+                AppendError as ManagedSliceAppendError,
+                ResizeError as ManagedSliceResizeError};
 pub use slotmap::{
     Key as SlotKey,
     Slot as SlotIndex,
@@ -23,5 +36,17 @@ pub use slotmap::{
 };
 #[cfg(feature = "map")]
 pub use map::{ManagedMap,
+              Error as ManagedMapError,
               Iter as ManagedMapIter,
-              IterMut as ManagedMapIterMut};
+              IntoIter as ManagedMapIntoIter,
+              IterMut as ManagedMapIterMut,
+              ExtractIf as ManagedMapExtractIf,
+              InsertError as ManagedMapInsertError,
+              OccupiedError as ManagedMapOccupiedError,
+              TryInsertError as ManagedMapTryInsertError,
+              EntryRef as ManagedMapEntryRef,
+              VacantEntryRef as ManagedMapVacantEntryRef};
+#[cfg(feature = "map")]
+pub use map_by::ManagedMapBy;
+#[cfg(all(feature = "map", feature = "bytemuck"))]
+pub use map::PackedError as ManagedMapPackedError;
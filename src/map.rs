@@ -1,19 +1,29 @@
 use core::mem;
 use core::fmt;
+use core::cmp::{self, Ordering};
+use core::hash::{Hash, Hasher};
 use core::slice;
 use core::borrow::Borrow;
-use core::ops::{Bound, RangeBounds};
+use core::ops::{Bound, Index, IndexMut, RangeBounds};
+
+use util::{RevOption, search_packed, pack_options};
+use error::CapacityError;
+use slice::ManagedSlice;
 
 #[cfg(feature = "std")]
 use std::collections::BTreeMap;
 #[cfg(feature = "std")]
 use std::collections::btree_map::{Iter as BTreeIter, IterMut as BTreeIterMut,
-                                  Range as BTreeRange};
+                                  IntoIter as BTreeIntoIter, Range as BTreeRange};
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::collections::btree_map::BTreeMap;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::collections::btree_map::{Iter as BTreeIter, IterMut as BTreeIterMut,
-                                    Range as BTreeRange};
+                                    IntoIter as BTreeIntoIter, Range as BTreeRange};
 
 /// A managed map.
 ///
@@ -31,33 +41,132 @@ use alloc::collections::btree_map::{Iter as BTreeIter, IterMut as BTreeIterMut,
 /// and a sorted slice of key-value pairs when it is not. Thus, algorithmic complexity
 /// of operations on it depends on the kind of map.
 ///
+/// The owned variant is always a `BTreeMap` in the global allocator, since selecting another
+/// type or allocator for it with a feature would change the enum for every crate using it, and
+/// features must be additive; custom allocators for `BTreeMap` are also unstable. Where
+/// a sorted vector is preferable, e.g. for its predictable memory use, or the entries have to
+/// live in a specific arena, the borrowed variant can be backed by a `Vec` or by a slice
+/// allocated in that arena; this uses the same algorithms as a map without allocation:
+///
+/// ```rust
+/// # use managed::ManagedMap;
+/// let mut pairs = vec![None; 16];
+/// let mut map = ManagedMap::from(&mut pairs[..]);
+/// map.insert("a", 1).unwrap();
+/// assert_eq!(map.get("a"), Some(&1));
+/// ```
+///
 /// A function that requires a managed object should be generic over an `Into<ManagedMap<'a, T>>`
 /// argument; then, it will be possible to pass either a `Vec<T>`, or a `&'a mut [T]`
 /// without any conversion at the call site.
 ///
+/// Iteration always yields the entries in ascending key order, for either variant, regardless
+/// of the order in which they were inserted or removed; so two maps with the same entries,
+/// borrowed or owned, iterate identically. Comparison and hashing only depend on the entries
+/// of the map in this order, so a borrowed and an owned map with the same entries compare
+/// and hash the same.
+///
+/// Both variants order keys by their `Ord` implementation. A borrowed map with a custom
+/// ordering can be made with [ManagedMapBy](struct.ManagedMapBy.html), which takes
+/// a comparator instead. `BTreeMap` cannot be given a comparator, so for a `ManagedMap`,
+/// a custom ordering is expressed by wrapping the key in a newtype that implements `Ord`
+/// as desired. For example, case-insensitive string keys:
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use managed::ManagedMap;
+/// #[derive(Debug, Clone, Copy)]
+/// struct CaseInsensitive<'s>(&'s str);
+///
+/// impl<'s> Ord for CaseInsensitive<'s> {
+///     fn cmp(&self, other: &Self) -> Ordering {
+///         let lhs = self.0.bytes().map(|c| c.to_ascii_lowercase());
+///         let rhs = other.0.bytes().map(|c| c.to_ascii_lowercase());
+///         lhs.cmp(rhs)
+///     }
+/// }
+/// # impl<'s> PartialOrd for CaseInsensitive<'s> {
+/// #     fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+/// # }
+/// # impl<'s> PartialEq for CaseInsensitive<'s> {
+/// #     fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+/// # }
+/// # impl<'s> Eq for CaseInsensitive<'s> {}
+///
+/// let mut pairs = [None; 4];
+/// let mut map = ManagedMap::from(&mut pairs[..]);
+/// map.insert(CaseInsensitive("Foo"), 1).unwrap();
+/// assert_eq!(map.get(&CaseInsensitive("FOO")), Some(&1));
+/// ```
+///
 /// See also [Managed](enum.Managed.html).
 pub enum ManagedMap<'a, K: 'a, V: 'a> {
-    /// Borrowed variant.
-    Borrowed(&'a mut [Option<(K, V)>]),
+    /// Borrowed variant, holding the backing slice and the number of entries in it.
+    ///
+    /// The entries are stored at the front of the slice in ascending key order, and
+    /// the rest of the slice is empty. Constructing this variant directly requires
+    /// upholding this; `From<&mut [Option<(K, V)>]>` counts the entries instead. A length
+    /// past the end of the slice never causes a panic: it is clamped to the length of the slice.
+    Borrowed(&'a mut [Option<(K, V)>], usize),
     /// Owned variant, only available with the `std` or `alloc` feature enabled.
     #[cfg(any(feature = "std", feature = "alloc"))]
     Owned(BTreeMap<K, V>)
 }
 
+/// Formats the entries of the map like `BTreeMap` does, regardless of the variant.
+///
+/// The alternate form (`{:#?}`) of a borrowed map instead shows every slot of the backing slice,
+/// including the empty ones, which is useful when debugging its packing.
 impl<'a, K: 'a, V: 'a> fmt::Debug for ManagedMap<'a, K, V>
         where K: fmt::Debug, V: fmt::Debug {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ManagedMap::Borrowed(x) => write!(f, "Borrowed({:?})", x),
+            ManagedMap::Borrowed(x, len) if f.alternate() =>
+                f.debug_tuple("Borrowed").field(x).field(len).finish(),
+            ManagedMap::Borrowed(x, len) =>
+                f.debug_map().entries(occupied(x, *len).iter().flatten().map(|(key, value)| (key, value))).finish(),
             #[cfg(any(feature = "std", feature = "alloc"))]
-            ManagedMap::Owned(x)    => write!(f, "Owned({:?})", x)
+            ManagedMap::Owned(x)    => x.fmt(f)
         }
     }
 }
 
+/// Creates a borrowed map from a slice whose entries are packed at the front in ascending
+/// key order, counting the entries, which takes O(n) time.
 impl<'a, K: 'a, V: 'a> From<&'a mut [Option<(K, V)>]> for ManagedMap<'a, K, V> {
     fn from(value: &'a mut [Option<(K, V)>]) -> Self {
-        ManagedMap::Borrowed(value)
+        let len = value.iter().take_while(|item| item.is_some()).count();
+        ManagedMap::Borrowed(value, len)
+    }
+}
+
+/// Creates a borrowed map from an array in the same way as from a slice.
+///
+/// The map itself does not record the length of the array in its type, so code that relies
+/// on the capacity at compile time should take the array, and convert it at the point of use.
+impl<'a, K: 'a, V: 'a, const N: usize> From<&'a mut [Option<(K, V)>; N]> for ManagedMap<'a, K, V> {
+    fn from(value: &'a mut [Option<(K, V)>; N]) -> Self {
+        ManagedMap::from(&mut value[..])
+    }
+}
+
+/// Converts a managed slice of pairs into a managed map.
+///
+/// A borrowed slice must already be sorted and packed, which is only checked in debug builds.
+/// An owned slice may be in any order; its entries are collected into an owned map, with
+/// later pairs replacing earlier pairs with the same key.
+impl<'a, K: Ord + 'a, V: 'a> From<ManagedSlice<'a, Option<(K, V)>>> for ManagedMap<'a, K, V> {
+    fn from(value: ManagedSlice<'a, Option<(K, V)>>) -> Self {
+        match value {
+            ManagedSlice::Borrowed(pairs) => {
+                let len = pairs.iter().take_while(|item| item.is_some()).count();
+                debug_assert!(is_sorted_and_packed(pairs, len),
+                              "pairs must be sorted by key and packed at the front");
+                ManagedMap::Borrowed(pairs, len)
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedSlice::Owned(pairs) => ManagedMap::Owned(pairs.into_iter().flatten().collect())
+        }
     }
 }
 
@@ -68,31 +177,227 @@ impl<'a, K: 'a, V: 'a> From<BTreeMap<K, V>> for ManagedMap<'a, K, V> {
     }
 }
 
-/// Like `Option`, but with `Some` values sorting first.
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-enum RevOption<T> {
-    Some(T),
-    None
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, K: Ord + 'a, V: 'a> ManagedMap<'a, K, V> {
+    /// Creates an empty owned map.
+    ///
+    /// This is only available with the `std` or `alloc` feature enabled; without them,
+    /// a borrowed map must be constructed from a slice.
+    pub fn new() -> Self {
+        ManagedMap::Owned(BTreeMap::new())
+    }
+
+    /// Returns the underlying `BTreeMap` of an owned map, or `None` for a borrowed map.
+    ///
+    /// This is only available with the `std` or `alloc` feature enabled.
+    pub fn into_btree_map(self) -> Option<BTreeMap<K, V>> {
+        match self {
+            ManagedMap::Borrowed(..) => None,
+            ManagedMap::Owned(map) => Some(map)
+        }
+    }
+
+    /// Returns an iterator over the entries of the map, in ascending value order.
+    ///
+    /// Entries with equal values are yielded in ascending key order. The entries have
+    /// to be collected and sorted first, so this takes O(n log n) time and allocates, and
+    /// is only available with the `std` or `alloc` feature enabled.
+    pub fn iter_by_value(&self) -> impl Iterator<Item = (&K, &V)>
+        where V: Ord
+    {
+        let mut entries: Vec<(&K, &V)> = self.iter().collect();
+        entries.sort_by(|lhs, rhs| lhs.1.cmp(rhs.1));
+        entries.into_iter()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, K: Ord + 'a, V: 'a> Default for ManagedMap<'a, K, V> {
+    fn default() -> Self {
+        ManagedMap::new()
+    }
+}
+
+/// Compares the entries of the maps in ascending key order, regardless of their variants.
+impl<'a, K: Ord + 'a, V: PartialEq + 'a> PartialEq for ManagedMap<'a, K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, K: Ord + 'a, V: Eq + 'a> Eq for ManagedMap<'a, K, V> {}
+
+/// Compares the entries of the maps lexicographically in ascending key order, as `BTreeMap` does.
+impl<'a, K: Ord + 'a, V: PartialOrd + 'a> PartialOrd for ManagedMap<'a, K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<'a, K: Ord + 'a, V: Ord + 'a> Ord for ManagedMap<'a, K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+/// Hashes the number of entries and then each entry in ascending key order, which gives
+/// the same hash as a `BTreeMap` with the same entries.
+impl<'a, K: Ord + Hash + 'a, V: Hash + 'a> Hash for ManagedMap<'a, K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for entry in self.iter() {
+            entry.hash(state)
+        }
+    }
+}
+
+/// Compares the entries of the map, regardless of the variant, with those of a `BTreeMap`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, K: Ord + 'a, V: PartialEq + 'a> PartialEq<BTreeMap<K, V>> for ManagedMap<'a, K, V> {
+    fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+/// Returns the value for a key, like `BTreeMap`.
+///
+/// # Panics
+///
+/// Panics if the key is not present in the map.
+impl<'a, K: Ord + Borrow<Q> + 'a, Q: Ord + ?Sized, V: 'a> Index<&Q> for ManagedMap<'a, K, V> {
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+/// Returns a mutable reference to the value for a key, which `BTreeMap` does not provide.
+///
+/// This can only change the value of a key that is already present; use `insert` to add
+/// a new key.
+///
+/// # Panics
+///
+/// Panics if the key is not present in the map.
+impl<'a, K: Ord + Borrow<Q> + 'a, Q: Ord + ?Sized, V: 'a> IndexMut<&Q> for ManagedMap<'a, K, V> {
+    fn index_mut(&mut self, key: &Q) -> &mut V {
+        self.get_mut(key).expect("no entry found for key")
+    }
+}
+
+/// An error returned by the checked operations of a managed map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The key is not present in the map.
+    NotFound,
+    /// There is no space left in the map.
+    Full,
+    /// The backing slice of a borrowed map is not sorted and packed.
+    Corrupt,
+    /// More than one pair has the same key.
+    DuplicateKey,
 }
 
-impl<T> From<Option<T>> for RevOption<T> {
-    fn from(other: Option<T>) -> Self {
-        match other {
-            Some(x) => RevOption::Some(x),
-            None => RevOption::None
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "key not found"),
+            Error::Full => write!(f, "map is full"),
+            Error::Corrupt => write!(f, "map is not sorted and packed"),
+            Error::DuplicateKey => write!(f, "duplicate key"),
         }
     }
 }
 
-impl<T> From<RevOption<T>> for Option<T> {
-    fn from(val: RevOption<T>) -> Self {
-        match val {
-            RevOption::Some(x) => Some(x),
-            RevOption::None => None
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// An error returned by `ManagedMap::checked_insert`, holding the reason the insertion failed
+/// and the pair that was not inserted.
+///
+/// Like `CapacityError`, this returns the pair rather than dropping it, so that it can be
+/// recovered with `into_inner` and e.g. inserted elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsertError<K, V> {
+    kind: Error,
+    pair: (K, V),
+}
+
+impl<K, V> InsertError<K, V> {
+    /// Creates an error of the given kind holding the rejected pair.
+    pub fn new(kind: Error, pair: (K, V)) -> Self {
+        InsertError { kind, pair }
+    }
+
+    /// Returns why the pair was not inserted.
+    pub fn kind(&self) -> Error {
+        self.kind
+    }
+
+    /// Returns the rejected pair.
+    pub fn into_inner(self) -> (K, V) {
+        self.pair
+    }
+}
+
+impl<K, V> fmt::Display for InsertError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: fmt::Debug, V: fmt::Debug> std::error::Error for InsertError<K, V> {}
+
+/// An error returned by `ManagedMap::try_insert` if the key is already present.
+///
+/// This holds the rejected pair, and a mutable reference to the value already in the map.
+#[derive(Debug)]
+pub struct OccupiedError<'m, K: 'm, V: 'm> {
+    /// The key that was not inserted.
+    pub key: K,
+    /// The value that was not inserted.
+    pub value: V,
+    /// The value already in the map for the key.
+    pub existing: &'m mut V,
+}
+
+impl<'m, K: fmt::Debug + 'm, V: fmt::Debug + 'm> fmt::Display for OccupiedError<'m, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to insert {:?}, key {:?} already exists with value {:?}",
+               self.value, self.key, self.existing)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'m, K: fmt::Debug + 'm, V: fmt::Debug + 'm> std::error::Error for OccupiedError<'m, K, V> {}
+
+/// An error returned by `ManagedMap::try_insert`.
+#[derive(Debug)]
+pub enum TryInsertError<'m, K: 'm, V: 'm> {
+    /// The key is already present in the map.
+    Occupied(OccupiedError<'m, K, V>),
+    /// The key is not present, and there is no space left in the map.
+    Full(CapacityError<(K, V)>),
+    /// The slot of the key in a borrowed map is free, so its backing slice is not sorted
+    /// and packed; this holds the pair that was not inserted.
+    Corrupt((K, V)),
+}
+
+impl<'m, K: fmt::Debug + 'm, V: fmt::Debug + 'm> fmt::Display for TryInsertError<'m, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TryInsertError::Occupied(err) => err.fmt(f),
+            TryInsertError::Full(err) => err.fmt(f),
+            TryInsertError::Corrupt(_) => Error::Corrupt.fmt(f),
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl<'m, K: fmt::Debug + 'm, V: fmt::Debug + 'm> std::error::Error for TryInsertError<'m, K, V> {}
+
 #[derive(Debug, Clone)]
 enum RangeInner<'a, K: 'a, V: 'a> {
     /// Borrowed variant.
@@ -147,8 +452,82 @@ impl<'a, K: 'a, V: 'a> DoubleEndedIterator for Range<'a, K, V> {
     }
 }
 
-fn binary_search_by_key_range<'a, K, V, Q: 'a, R>(slice: &[Option<(K, V)>], range: R) -> Result<(usize, usize), ()>
-    where K: Ord + Borrow<Q>, Q: Ord + ?Sized, R: RangeBounds<Q>
+/// A view into a single entry of a map, which is either occupied or vacant, that only
+/// converts the key it was looked up with into an owned key when a vacant entry is filled.
+///
+/// This is created by [`ManagedMap::entry_ref`](enum.ManagedMap.html#method.entry_ref).
+pub enum EntryRef<'m, 'q, K: 'm, V: 'm, Q: ?Sized + 'q> {
+    /// The key is present, and this is its value.
+    Occupied(&'m mut V),
+    /// The key is not present.
+    Vacant(VacantEntryRef<'m, 'q, K, V, Q>)
+}
+
+impl<'m, 'q, K: Ord + From<&'q Q> + 'm, V: 'm, Q: ?Sized + 'q> EntryRef<'m, 'q, K, V, Q> {
+    /// Returns the value, inserting `default` if the entry is vacant.
+    ///
+    /// Returns `default` in a `CapacityError` if the entry is vacant and the map is full.
+    pub fn or_insert(self, default: V) -> Result<&'m mut V, CapacityError<V>> {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the value, inserting the result of `f` if the entry is vacant.
+    ///
+    /// Returns the result of `f` in a `CapacityError` if the entry is vacant and the map is full.
+    pub fn or_insert_with<F>(self, f: F) -> Result<&'m mut V, CapacityError<V>>
+        where F: FnOnce() -> V
+    {
+        match self {
+            EntryRef::Occupied(value) => Ok(value),
+            EntryRef::Vacant(entry) => entry.insert(f())
+        }
+    }
+}
+
+enum VacantInner<'m, K: 'm, V: 'm> {
+    Borrowed { pairs: &'m mut [Option<(K, V)>], len: &'m mut usize, idx: usize },
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Owned(&'m mut BTreeMap<K, V>),
+}
+
+/// A vacant entry of a map, holding the key it was looked up with.
+pub struct VacantEntryRef<'m, 'q, K: 'm, V: 'm, Q: ?Sized + 'q> {
+    inner: VacantInner<'m, K, V>,
+    key: &'q Q
+}
+
+impl<'m, 'q, K: Ord + From<&'q Q> + 'm, V: 'm, Q: ?Sized + 'q> VacantEntryRef<'m, 'q, K, V, Q> {
+    /// Returns the key the entry was looked up with.
+    pub fn key(&self) -> &'q Q {
+        self.key
+    }
+
+    /// Converts the key into an owned key and inserts it with `value`, returning
+    /// a reference to the value.
+    ///
+    /// Returns `value` in a `CapacityError`, without converting the key, if the map is full.
+    pub fn insert(self, value: V) -> Result<&'m mut V, CapacityError<V>> {
+        match self.inner {
+            VacantInner::Borrowed { pairs, len, idx } => {
+                if *len >= pairs.len() {
+                    return Err(CapacityError::new(value))
+                }
+                match shift_insert(pairs, *len, idx, (K::from(self.key), value)) {
+                    Ok(&mut (_, ref mut value)) => {
+                        *len += 1;
+                        Ok(value)
+                    }
+                    Err((_, value)) => Err(CapacityError::new(value))
+                }
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            VacantInner::Owned(map) => Ok(map.entry(K::from(self.key)).or_insert(value))
+        }
+    }
+}
+
+fn binary_search_by_key_range<'a, K, V, Q, R>(slice: &[Option<(K, V)>], range: R) -> Result<(usize, usize), ()>
+    where K: Ord + Borrow<Q>, Q: Ord + ?Sized + 'a, R: RangeBounds<Q>
 {
     if slice.is_empty() {
         return Err(())
@@ -177,7 +556,7 @@ fn binary_search_by_key_range<'a, K, V, Q: 'a, R>(slice: &[Option<(K, V)>], rang
                     Bound::Unbounded => unreachable!()
                 }
             }
-        };
+        }
         while left < right {
             let middle = left + (right - left) / 2;
             if is_before_range!(key!(slice[middle])) {
@@ -208,7 +587,7 @@ fn binary_search_by_key_range<'a, K, V, Q: 'a, R>(slice: &[Option<(K, V)>], rang
                     Bound::Unbounded => unreachable!()
                 }
             }
-        };
+        }
         right = slice.len(); // no need to reset left
         while left < right {
             let middle = left + (right - left + 1) / 2;
@@ -230,35 +609,187 @@ fn binary_search_by_key_range<'a, K, V, Q: 'a, R>(slice: &[Option<(K, V)>], rang
     Ok((begin, end))
 }
 
-fn binary_search_by_key<K, V, Q>(slice: &[Option<(K, V)>], key: &Q) -> Result<usize, usize>
-    where K: Ord + Borrow<Q>, Q: Ord + ?Sized
-{
-    slice.binary_search_by_key(&RevOption::Some(key), |entry| {
-        entry.as_ref().map(|(key, _)| key.borrow()).into()
-    })
-}
-
 fn pair_by_key<'a, K, Q, V>(slice: &'a [Option<(K, V)>], key: &Q) ->
                            Result<&'a (K, V), usize>
     where K: Ord + Borrow<Q>, Q: Ord + ?Sized
 {
-    binary_search_by_key(slice, key).map(move |idx| slice[idx].as_ref().unwrap())
+    search_packed(slice, key).and_then(move |idx| slice[idx].as_ref().ok_or(idx))
 }
 
 fn pair_mut_by_key<'a, K, Q, V>(slice: &'a mut [Option<(K, V)>], key: &Q) ->
                                Result<&'a mut (K, V), usize>
     where K: Ord + Borrow<Q>, Q: Ord + ?Sized
 {
-    binary_search_by_key(slice, key).map(move |idx| slice[idx].as_mut().unwrap())
+    search_packed(slice, key).and_then(move |idx| slice[idx].as_mut().ok_or(idx))
+}
+
+/// Moves `slots[idx..len]` one slot to the right and stores `item` at `idx`.
+///
+/// The slot at `len` must be free, and is overwritten. Returns `item` back if `idx > len`
+/// or there is no slot at `len`.
+pub(crate) fn shift_insert<T>(slots: &mut [Option<T>], len: usize, idx: usize, item: T) -> Result<&mut T, T> {
+    if idx > len || len >= slots.len() {
+        return Err(item)
+    }
+    slots[idx..len + 1].rotate_right(1);
+    Ok(slots[idx].insert(item))
+}
+
+/// Takes the item at `idx` and moves `slots[idx + 1..len]` one slot to the left, leaving
+/// the slot at `len - 1` free.
+///
+/// Returns `None`, without moving anything, if `idx >= len`, `len > slots.len()`,
+/// or the slot at `idx` is already free.
+pub(crate) fn shift_remove<T>(slots: &mut [Option<T>], len: usize, idx: usize) -> Option<T> {
+    if idx >= len || len > slots.len() {
+        return None
+    }
+    let item = slots[idx].take()?;
+    slots[idx..len].rotate_left(1);
+    Some(item)
+}
+
+/// A slot of a borrowed map whose pair has been taken out, e.g. to pass its value to
+/// a closure by value.
+///
+/// If the slot is still free when this is dropped, which happens if the closure panics
+/// before `fill` is called, it is removed; the entries after it are shifted back by one,
+/// so the map stays packed, but the pair that was taken out is lost.
+struct Hole<'p, K: 'p, V: 'p> {
+    pairs: &'p mut [Option<(K, V)>],
+    len: &'p mut usize,
+    idx: usize,
+}
+
+impl<'p, K: 'p, V: 'p> Hole<'p, K, V> {
+    fn fill(self, pair: (K, V)) {
+        self.pairs[self.idx] = Some(pair)
+    }
+}
+
+impl<'p, K: 'p, V: 'p> Drop for Hole<'p, K, V> {
+    fn drop(&mut self) {
+        let len = occupied(self.pairs, *self.len).len();
+        if self.idx < len && self.pairs[self.idx].is_none() {
+            self.pairs[self.idx..len].rotate_left(1);
+            *self.len = len - 1
+        }
+    }
+}
+
+/// The index where a pair was stored and the value it replaced, or the rejected pair.
+type InsertResult<K, V> = Result<(usize, Option<V>), (Error, (K, V))>;
+type IndexedInsertResult<K, V> = Result<(usize, Option<V>), CapacityError<(K, V)>>;
+
+fn insert_pair<K: Ord, V>(pairs: &mut [Option<(K, V)>], len: &mut usize,
+                          key: K, new_value: V) -> InsertResult<K, V> {
+    match search_packed(occupied(pairs, *len), &key) {
+        Err(idx) => {
+            // Only the entries after `idx` are shifted, moving the first `None` into
+            // `pairs[idx]`; if there are none, the pair is written in place.
+            match shift_insert(pairs, *len, idx, (key, new_value)) {
+                Ok(_) => {
+                    *len += 1;
+                    Ok((idx, None))
+                }
+                Err(pair) => Err((Error::Full, pair))
+            }
+        }
+        Ok(idx) => {
+            match pairs[idx] {
+                Some(ref mut pair) => {
+                    let (_key, value) = mem::replace(pair, (key, new_value));
+                    Ok((idx, Some(value)))
+                }
+                None => Err((Error::Corrupt, (key, new_value)))
+            }
+        }
+    }
+}
+
+fn remove_pair<K, Q, V>(pairs: &mut [Option<(K, V)>], len: &mut usize, key: &Q) -> Result<V, Error>
+    where K: Ord + Borrow<Q>, Q: Ord + ?Sized
+{
+    match search_packed(occupied(pairs, *len), key) {
+        Ok(idx) => {
+            match shift_remove(pairs, *len, idx) {
+                Some((_key, value)) => {
+                    *len -= 1;
+                    Ok(value)
+                }
+                None => Err(Error::Corrupt)
+            }
+        }
+        Err(_) => Err(Error::NotFound)
+    }
+}
+
+/// Sorts the `Some` entries by key and packs them at the front.
+///
+/// Returns `Error::DuplicateKey` if two entries have the same key; the entries are
+/// sorted and packed regardless.
+fn sort_and_pack<K: Ord, V>(pairs: &mut [Option<(K, V)>]) -> Result<(), Error> {
+    pairs.sort_unstable_by(|lhs, rhs| {
+        let lhs: RevOption<&K> = lhs.as_ref().map(|(key, _)| key).into();
+        let rhs: RevOption<&K> = rhs.as_ref().map(|(key, _)| key).into();
+        lhs.cmp(&rhs)
+    });
+    let has_duplicates = pairs.windows(2).any(|window| {
+        match (&window[0], &window[1]) {
+            (&Some((ref lhs, _)), &Some((ref rhs, _))) => lhs == rhs,
+            _ => false
+        }
+    });
+    if has_duplicates {
+        Err(Error::DuplicateKey)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the occupied slots of the backing slice of a borrowed map.
+///
+/// The stored length can be set directly, so it is clamped to the length of the slice rather
+/// than trusted; every method on a borrowed map reads the entries through this.
+pub(crate) fn occupied<K, V>(pairs: &[Option<(K, V)>], len: usize) -> &[Option<(K, V)>] {
+    &pairs[..cmp::min(len, pairs.len())]
+}
+
+/// Like `occupied`, but returns a mutable slice.
+pub(crate) fn occupied_mut<K, V>(pairs: &mut [Option<(K, V)>], len: usize) -> &mut [Option<(K, V)>] {
+    let len = cmp::min(len, pairs.len());
+    &mut pairs[..len]
+}
+
+/// Checks that the first `len` entries are `Some` and strictly sorted by key, and that
+/// the rest are `None`.
+fn is_sorted_and_packed<K: Ord, V>(pairs: &[Option<(K, V)>], len: usize) -> bool {
+    if len > pairs.len() {
+        return false
+    }
+    let (entries, rest) = pairs.split_at(len);
+    entries.iter().all(|item| item.is_some()) &&
+        rest.iter().all(|item| item.is_none()) &&
+        entries.windows(2).all(|window| {
+            match (&window[0], &window[1]) {
+                (&Some((ref lhs, _)), &Some((ref rhs, _))) => lhs < rhs,
+                _ => false
+            }
+        })
 }
 
 impl<'a, K: Ord + 'a, V: 'a> ManagedMap<'a, K, V> {
+    /// Removes all entries from the map.
+    ///
+    /// For a borrowed map, only the occupied slots are overwritten, with a plain fill
+    /// that does not inspect them.
     pub fn clear(&mut self) {
         match self {
-            &mut ManagedMap::Borrowed(ref mut pairs) => {
-                for item in pairs.iter_mut() {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                for item in occupied_mut(pairs, *len).iter_mut() {
                     *item = None
                 }
+                *len = 0
             },
             #[cfg(any(feature = "std", feature = "alloc"))]
             &mut ManagedMap::Owned(ref mut map) => map.clear()
@@ -269,8 +800,8 @@ impl<'a, K: Ord + 'a, V: 'a> ManagedMap<'a, K, V> {
         where K: Borrow<Q>, Q: Ord + ?Sized
     {
         match self {
-            ManagedMap::Borrowed(pairs) => {
-                match pair_by_key(pairs, key.borrow()) {
+            ManagedMap::Borrowed(pairs, len) => {
+                match pair_by_key(occupied(pairs, *len), key.borrow()) {
                     Ok((_, value)) => Some(value),
                     Err(_) => None
                 }
@@ -280,12 +811,31 @@ impl<'a, K: Ord + 'a, V: 'a> ManagedMap<'a, K, V> {
         }
     }
 
+    /// Returns the value for `key`, or `default` if it is not present.
+    ///
+    /// Unlike `get_or_insert`, this never modifies the map.
+    pub fn get_or<'s, Q>(&'s self, key: &Q, default: &'s V) -> &'s V
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// Returns a mutable reference to the value for `key`, or `default` if it is not present.
+    ///
+    /// Unlike `get_or_insert`, this never modifies the map, so writes for a missing key
+    /// go to `default` even if the map is full.
+    pub fn get_mut_or<'s, Q>(&'s mut self, key: &Q, default: &'s mut V) -> &'s mut V
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
+        self.get_mut(key).unwrap_or(default)
+    }
+
     pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
         where K: Borrow<Q>, Q: Ord + ?Sized
     {
         match self {
-            &mut ManagedMap::Borrowed(ref mut pairs) => {
-                match pair_mut_by_key(pairs, key.borrow()) {
+            &mut ManagedMap::Borrowed(ref mut pairs, len) => {
+                match pair_mut_by_key(occupied_mut(pairs, len), key.borrow()) {
                     Ok(&mut (_, ref mut value)) => Some(value),
                     Err(_) => None
                 }
@@ -295,713 +845,3775 @@ impl<'a, K: Ord + 'a, V: 'a> ManagedMap<'a, K, V> {
         }
     }
 
-    pub fn range<'b, 'c, Q: 'c, R>(&'b self, range: R) -> Range<'a, K, V>
-            where K: Borrow<Q>, Q: Ord + ?Sized, R: RangeBounds<Q>, 'b: 'a
+    /// Returns the key and a mutable reference to the value for `key`.
+    ///
+    /// The key is only borrowed immutably, since changing it could break the ordering
+    /// of the map; this is useful e.g. for reading data cached in the key while updating
+    /// the value.
+    pub fn get_pair_mut<Q>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+        where K: Borrow<Q>, Q: Ord + ?Sized
     {
         match self {
-            ManagedMap::Borrowed(pairs) => {
-                match binary_search_by_key_range(&pairs[0..self.len()], range) {
-                    Ok((begin, end)) => Range(RangeInner::Borrowed {
-                        slice: &pairs[begin..end], begin: 0, end: end-begin }),
-                    Err(()) => Range(RangeInner::Borrowed {
-                        slice: &[], begin: 0, end: 0 }),
+            &mut ManagedMap::Borrowed(ref mut pairs, len) => {
+                match pair_mut_by_key(occupied_mut(pairs, len), key.borrow()) {
+                    Ok(&mut (ref key, ref mut value)) => Some((key, value)),
+                    Err(_) => None
                 }
             },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            ManagedMap::Owned(map) => {
-                Range(RangeInner::Owned(map.range(range)))
-            },
+            &mut ManagedMap::Owned(ref mut map) =>
+                map.range_mut((Bound::Included(key), Bound::Included(key))).next()
+        }
+    }
+
+    /// Looks up each of `keys`, yielding the value for each key in turn, or `None` if it
+    /// is not present.
+    ///
+    /// This does not allocate, so it is available without the `std` or `alloc` feature.
+    pub fn get_each<'s, Q, I>(&'s self, keys: I) -> impl Iterator<Item = Option<&'s V>> + 's
+        where K: Borrow<Q>, Q: Ord + ?Sized + 's, I: IntoIterator<Item = &'s Q>, I::IntoIter: 's
+    {
+        keys.into_iter().map(move |key| self.get(key))
+    }
+
+    /// Returns whether any value in the map is equal to `value`.
+    ///
+    /// Unlike looking up a key, this is a linear scan, and takes O(n) time for either variant.
+    pub fn contains_value(&self, value: &V) -> bool
+        where V: PartialEq
+    {
+        self.iter().any(|(_, other)| other == value)
+    }
+
+    /// Returns the entry at position `idx` in sorted order.
+    ///
+    /// This takes O(1) time for a borrowed map and O(n) time for an owned map.
+    pub fn get_index(&self, idx: usize) -> Option<(&K, &V)> {
+        match self {
+            ManagedMap::Borrowed(pairs, len) =>
+                occupied(pairs, *len).get(idx).and_then(|pair| pair.as_ref()).map(|(key, value)| (key, value)),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(map) => map.iter().nth(idx)
         }
     }
 
-    pub fn insert(&mut self, key: K, new_value: V) -> Result<Option<V>, (K, V)> {
+    /// Returns the entry at position `idx` in sorted order, with a mutable reference to
+    /// the value.
+    ///
+    /// This takes O(1) time for a borrowed map and O(n) time for an owned map.
+    pub fn get_index_mut(&mut self, idx: usize) -> Option<(&K, &mut V)> {
         match self {
-            &mut ManagedMap::Borrowed(ref mut pairs) if pairs.is_empty() =>
-                Err((key, new_value)), // no space at all
-            &mut ManagedMap::Borrowed(ref mut pairs) => {
-                match binary_search_by_key(pairs, &key) {
-                    Err(_) if pairs[pairs.len() - 1].is_some() =>
-                        Err((key, new_value)), // full
-                    Err(idx) => {
-                        let rotate_by = pairs.len() - idx - 1;
-                        pairs[idx..].rotate_left(rotate_by);
-                        assert!(pairs[idx].is_none(), "broken invariant");
-                        pairs[idx] = Some((key, new_value));
-                        Ok(None)
-                    }
-                    Ok(idx) => {
-                        let mut swap_pair = Some((key, new_value));
-                        mem::swap(&mut pairs[idx], &mut swap_pair);
-                        let (_key, value) = swap_pair.expect("broken invariant");
-                        Ok(Some(value))
-                    }
-                }
-            },
+            ManagedMap::Borrowed(pairs, len) =>
+                occupied_mut(pairs, *len).get_mut(idx).and_then(|pair| pair.as_mut()).map(|(key, value)| (&*key, value)),
             #[cfg(any(feature = "std", feature = "alloc"))]
-            &mut ManagedMap::Owned(ref mut map) => Ok(map.insert(key, new_value))
+            ManagedMap::Owned(map) => map.iter_mut().nth(idx)
         }
     }
 
-    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    /// Swaps the values for keys `a` and `b`, leaving the keys in place.
+    ///
+    /// Returns `false`, without changing the map, unless both keys are present.
+    pub fn swap_values<Q>(&mut self, a: &Q, b: &Q) -> bool
         where K: Borrow<Q>, Q: Ord + ?Sized
     {
+        if a == b {
+            return self.get(a).is_some()
+        }
         match self {
-            &mut ManagedMap::Borrowed(ref mut pairs) => {
-                match binary_search_by_key(pairs, key) {
-                    Ok(idx) => {
-                        let (_key, value) = pairs[idx].take().expect("broken invariant");
-                        pairs[idx..].rotate_left(1);
-                        Some(value)
-                    }
-                    Err(_) => None
+            &mut ManagedMap::Borrowed(ref mut pairs, len) => {
+                let pairs = occupied_mut(pairs, len);
+                let (a, b) = match (search_packed(pairs, a), search_packed(pairs, b)) {
+                    (Ok(a), Ok(b)) => (a.min(b), a.max(b)),
+                    _ => return false
+                };
+                let (left, right) = pairs.split_at_mut(b);
+                match (&mut left[a], &mut right[0]) {
+                    (&mut Some((_, ref mut lhs)), &mut Some((_, ref mut rhs))) => {
+                        mem::swap(lhs, rhs);
+                        true
+                    },
+                    _ => false
                 }
             },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            &mut ManagedMap::Owned(ref mut map) => map.remove(key)
+            &mut ManagedMap::Owned(ref mut map) => {
+                // Both values are borrowed at once from the ends of the range between the keys.
+                let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+                let mut range = map.range_mut((Bound::Included(lo), Bound::Included(hi)));
+                match (range.next(), range.next_back()) {
+                    (Some((first, lhs)), Some((last, rhs)))
+                            if first.borrow() == lo && last.borrow() == hi => {
+                        mem::swap(lhs, rhs);
+                        true
+                    },
+                    _ => false
+                }
+            }
         }
     }
 
-    /// ManagedMap contains no elements?
-    pub fn is_empty(&self) -> bool {
+    /// Returns the entry with the least key greater than or equal to `key`, if any.
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
         match self {
-            ManagedMap::Borrowed(pairs) =>
-                pairs.iter().all(|item| item.is_none()),
+            ManagedMap::Borrowed(pairs, len) => {
+                let idx = search_packed(occupied(pairs, *len), key).unwrap_or_else(|idx| idx);
+                self.get_index(idx)
+            },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            ManagedMap::Owned(map) =>
-                map.is_empty()
+            ManagedMap::Owned(map) => map.range((Bound::Included(key), Bound::Unbounded)).next()
         }
     }
 
-    /// Returns the number of elements in the ManagedMap.
-    pub fn len(&self) -> usize {
+    /// Returns the entry with the greatest key less than or equal to `key`, if any.
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
         match self {
-            ManagedMap::Borrowed(pairs) =>
-                pairs.iter()
-                .take_while(|item| item.is_some())
-                .count(),
+            ManagedMap::Borrowed(pairs, len) => {
+                match search_packed(occupied(pairs, *len), key) {
+                    Ok(idx) => self.get_index(idx),
+                    Err(0) => None,
+                    Err(idx) => self.get_index(idx - 1)
+                }
+            },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            ManagedMap::Owned(map) =>
-                map.len()
+            ManagedMap::Owned(map) => map.range((Bound::Unbounded, Bound::Included(key))).next_back()
         }
     }
 
-    pub fn iter(&self) -> Iter<K, V> {
+    /// Returns an iterator over the entries with keys in `range`, in ascending key order.
+    pub fn range<'b, 'c, Q, R>(&'b self, range: R) -> Range<'a, K, V>
+            where K: Borrow<Q>, Q: Ord + ?Sized + 'c, R: RangeBounds<Q>, 'b: 'a
+    {
         match self {
-            ManagedMap::Borrowed(pairs) =>
-                Iter::Borrowed(pairs.iter()),
+            ManagedMap::Borrowed(pairs, len) => {
+                match binary_search_by_key_range(occupied(pairs, *len), range) {
+                    Ok((begin, end)) => Range(RangeInner::Borrowed {
+                        slice: &pairs[begin..end], begin: 0, end: end-begin }),
+                    Err(()) => Range(RangeInner::Borrowed {
+                        slice: &[], begin: 0, end: 0 }),
+                }
+            },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            ManagedMap::Owned(map) =>
-                Iter::Owned(map.iter()),
+            ManagedMap::Owned(map) => {
+                Range(RangeInner::Owned(map.range(range)))
+            },
         }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<K, V> {
+    /// Returns iterators over the entries with keys less than `key`, and over those with keys
+    /// greater than or equal to it.
+    ///
+    /// For a borrowed map, the split point is binary searched once, and both iterators
+    /// borrow a part of the backing slice. This does not allocate, nor build new maps.
+    pub fn iter_split_at<Q>(&self, key: &Q) -> (Range<'_, K, V>, Range<'_, K, V>)
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
         match self {
-            &mut ManagedMap::Borrowed(ref mut pairs) =>
-                IterMut::Borrowed(pairs.iter_mut()),
+            ManagedMap::Borrowed(pairs, len) => {
+                let pairs = occupied(pairs, *len);
+                let idx = search_packed(pairs, key).unwrap_or_else(|idx| idx);
+                (Range(RangeInner::Borrowed { slice: &pairs[..idx], begin: 0, end: idx }),
+                 Range(RangeInner::Borrowed { slice: &pairs[idx..], begin: 0, end: pairs.len() - idx }))
+            },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            &mut ManagedMap::Owned(ref mut map) =>
-                IterMut::Owned(map.iter_mut()),
+            ManagedMap::Owned(map) => {
+                (Range(RangeInner::Owned(map.range((Bound::Unbounded, Bound::Excluded(key))))),
+                 Range(RangeInner::Owned(map.range((Bound::Included(key), Bound::Unbounded)))))
+            },
         }
     }
-}
-
-pub enum Iter<'a, K: 'a, V: 'a> {
-    /// Borrowed variant.
-    Borrowed(slice::Iter<'a, Option<(K, V)>>),
-    /// Owned variant, only available with the `std` or `alloc` feature enabled.
-    #[cfg(any(feature = "std", feature = "alloc"))]
-    Owned(BTreeIter<'a, K, V>),
-}
 
-impl<'a, K: Ord + 'a, V: 'a> Iterator for Iter<'a, K, V> {
-    type Item = (&'a K, &'a V);
+    /// Returns the number of entries with keys in `range`.
+    ///
+    /// For a borrowed map, this only binary searches the bounds of the range, and takes
+    /// O(log n) time. For an owned map, the entries in the range are counted one by one.
+    pub fn count_range<Q, R>(&self, range: R) -> usize
+        where K: Borrow<Q>, Q: Ord + ?Sized, R: RangeBounds<Q>
+    {
+        match self {
+            ManagedMap::Borrowed(pairs, len) => {
+                match binary_search_by_key_range(occupied(pairs, *len), range) {
+                    Ok((begin, end)) => end - begin,
+                    Err(()) => 0
+                }
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(map) => map.range(range).count()
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Inserts a pair, returning the value it replaced, if any.
+    ///
+    /// Returns the pair in a `CapacityError` if the key is not present and the map is full.
+    /// For a borrowed map, this takes O(log n + m) time, where m is the number of entries with greater keys,
+    /// which are shifted by one slot; in particular, inserting the greatest key shifts nothing.
+    pub fn insert(&mut self, key: K, new_value: V) -> Result<Option<V>, CapacityError<(K, V)>> {
         match self {
-            &mut Iter::Borrowed(ref mut iter) =>
-                match iter.next() {
-                    Some(&Some((ref k, ref v))) => Some((k, v)),
-                    Some(&None) => None,
-                    None => None,
-                },
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) =>
+                insert_pair(pairs, len, key, new_value)
+                    .map(|(_idx, old_value)| old_value)
+                    .map_err(|(_err, pair)| CapacityError::new(pair)),
             #[cfg(any(feature = "std", feature = "alloc"))]
-            &mut Iter::Owned(ref mut iter) =>
-                iter.next(),
+            &mut ManagedMap::Owned(ref mut map) => Ok(map.insert(key, new_value))
         }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
+    /// Inserts a pair if the key is not present, returning a reference to the value.
+    ///
+    /// Unlike `insert`, this never replaces a value; if the key is present, the pair is
+    /// returned in `TryInsertError::Occupied` along with the value already in the map.
+    /// If the key is not present and the map is full, the pair is returned in
+    /// `TryInsertError::Full`, and if the slot of the key in a borrowed map is found to be
+    /// free, in `TryInsertError::Corrupt`.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, TryInsertError<'_, K, V>> {
         match self {
-            Iter::Borrowed(iter) => {
-                let len = iter.clone()
-                    .take_while(|item| item.is_some())
-                    .count();
-                (len, Some(len))
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                match search_packed(occupied(pairs, *len), &key) {
+                    Ok(idx) => match pairs[idx] {
+                        Some((_, ref mut existing)) =>
+                            Err(TryInsertError::Occupied(OccupiedError { key, value, existing })),
+                        None => Err(TryInsertError::Corrupt((key, value)))
+                    },
+                    Err(idx) => match shift_insert(pairs, *len, idx, (key, value)) {
+                        Ok(&mut (_, ref mut value)) => {
+                            *len += 1;
+                            Ok(value)
+                        }
+                        Err(pair) => Err(TryInsertError::Full(CapacityError::new(pair)))
+                    }
+                }
             },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            Iter::Owned(iter) =>
-                iter.size_hint(),
+            &mut ManagedMap::Owned(ref mut map) => {
+                if map.contains_key(&key) {
+                    let existing = map.get_mut(&key).unwrap();
+                    Err(TryInsertError::Occupied(OccupiedError { key, value, existing }))
+                } else {
+                    Ok(map.entry(key).or_insert(value))
+                }
+            }
         }
     }
-}
-
-pub enum IterMut<'a, K: 'a, V: 'a> {
-    /// Borrowed variant.
-    Borrowed(slice::IterMut<'a, Option<(K, V)>>),
-    /// Owned variant, only available with the `std` or `alloc` feature enabled.
-    #[cfg(any(feature = "std", feature = "alloc"))]
-    Owned(BTreeIterMut<'a, K, V>),
-}
-
-impl<'a, K: Ord + 'a, V: 'a> Iterator for IterMut<'a, K, V> {
-    type Item = (&'a K, &'a mut V);
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Like `insert`, but also returns the index at which the pair is stored.
+    ///
+    /// For a borrowed map, this is the position of the pair in the backing slice after
+    /// insertion; pairs after it have shifted by one if the key was not already present.
+    /// For an owned map, this is the position of the pair in iteration order.
+    pub fn insert_indexed(&mut self, key: K, new_value: V) -> IndexedInsertResult<K, V> {
         match self {
-            &mut IterMut::Borrowed(ref mut iter) =>
-                match iter.next() {
-                    Some(&mut Some((ref k, ref mut v))) => Some((k, v)),
-                    Some(&mut None) => None,
-                    None => None,
-                },
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) =>
+                insert_pair(pairs, len, key, new_value)
+                    .map_err(|(_err, pair)| CapacityError::new(pair)),
             #[cfg(any(feature = "std", feature = "alloc"))]
-            &mut IterMut::Owned(ref mut iter) =>
-                iter.next(),
+            &mut ManagedMap::Owned(ref mut map) => {
+                let idx = map.range(..&key).count();
+                Ok((idx, map.insert(key, new_value)))
+            }
         }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
+    /// Inserts pairs from an iterator until the map is full.
+    ///
+    /// Returns the number of pairs that were inserted, including those that replaced the value
+    /// of a key already present. Insertion stops at the first pair that does not fit; that pair
+    /// and the rest of the iterator are dropped.
+    pub fn insert_many<I>(&mut self, iter: I) -> usize
+        where I: IntoIterator<Item = (K, V)>
+    {
+        let mut count = 0;
+        for (key, value) in iter {
+            if self.insert(key, value).is_err() {
+                break
+            }
+            count += 1
+        }
+        count
+    }
+
+    /// Inserts a pair, merging the values if the key is already present.
+    ///
+    /// If the key is present, its value is replaced with `merge(old_value, value)`
+    /// and the existing key is kept. Otherwise the pair is inserted as with `insert`.
+    /// Returns the pair in a `CapacityError` if the key is not present and the map is full.
+    ///
+    /// If `merge` panics, the entry for the key is removed from the map, which otherwise
+    /// stays valid.
+    pub fn insert_with<F>(&mut self, key: K, value: V, merge: F) -> Result<(), CapacityError<(K, V)>>
+        where F: FnOnce(V, V) -> V
+    {
         match self {
-            IterMut::Borrowed(iter) => {
-                let (_, upper) = iter.size_hint();
-                (0, upper)
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                match search_packed(occupied(pairs, *len), &key) {
+                    Ok(idx) => match pairs[idx].take() {
+                        Some((old_key, old_value)) => {
+                            let hole = Hole { pairs, len, idx };
+                            let new_value = merge(old_value, value);
+                            hole.fill((old_key, new_value));
+                            Ok(())
+                        }
+                        // `search_packed` only finds a key in an occupied slot.
+                        None => Err(CapacityError::new((key, value)))
+                    },
+                    Err(_) => insert_pair(pairs, len, key, value)
+                        .map(|_| ())
+                        .map_err(|(_err, pair)| CapacityError::new(pair))
+                }
             },
             #[cfg(any(feature = "std", feature = "alloc"))]
-            IterMut::Owned(iter) =>
-                iter.size_hint(),
+            &mut ManagedMap::Owned(ref mut map) => {
+                match map.remove_entry(&key) {
+                    Some((old_key, old_value)) => map.insert(old_key, merge(old_value, value)),
+                    None => map.insert(key, value)
+                };
+                Ok(())
+            }
         }
     }
-}
 
-// LCOV_EXCL_START
-#[cfg(test)]
-mod test {
-    use super::ManagedMap;
-    use core::ops::Bound::*;
+    /// Returns the value for `key`, inserting `default` if it is not present.
+    ///
+    /// Returns `None` only if the key is not present and the map is full.
+    pub fn get_or_insert(&mut self, key: K, default: V) -> Option<&mut V> {
+        self.get_or_insert_with(key, || default)
+    }
 
-    fn all_pairs_empty() -> [Option<(&'static str, u32)>; 4] {
-        [None; 4]
+    /// Returns the value for `key`, inserting the result of `f` if it is not present.
+    ///
+    /// Returns `None` only if the key is not present and the map is full;
+    /// in that case, `f` is not called.
+    pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> Option<&mut V>
+        where F: FnOnce() -> V
+    {
+        self.try_get_or_insert_with(key, f).ok()
     }
 
-    fn one_pair_full() -> [Option<(&'static str, u32)>; 4] {
-        [Some(("a", 1)), None, None, None]
+    /// Like `get_or_insert_with`, but returns the key in a `CapacityError` if it is not present
+    /// and the map is full; in that case, `f` is not called.
+    ///
+    /// This never panics.
+    pub fn try_get_or_insert_with<F>(&mut self, key: K, f: F) -> Result<&mut V, CapacityError<K>>
+        where F: FnOnce() -> V
+    {
+        self.try_get_or_insert_with_key(key, |_| f())
     }
 
-    fn all_pairs_full() -> [Option<(&'static str, u32)>; 4] {
-        [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), Some(("d", 4))]
+    /// Like `get_or_insert_with`, but `f` is passed a reference to the key, so that the value
+    /// can be derived from it.
+    ///
+    /// Returns `None` only if the key is not present and the map is full;
+    /// in that case, `f` is not called.
+    pub fn get_or_insert_with_key<F>(&mut self, key: K, f: F) -> Option<&mut V>
+        where F: FnOnce(&K) -> V
+    {
+        self.try_get_or_insert_with_key(key, f).ok()
     }
 
-    fn unwrap<'a, K, V>(map: &'a ManagedMap<'a, K, V>) -> &'a [Option<(K, V)>] {
-        match map {
-            ManagedMap::Borrowed(map) => map,
-            _ => unreachable!()
+    fn try_get_or_insert_with_key<F>(&mut self, key: K, f: F) -> Result<&mut V, CapacityError<K>>
+        where F: FnOnce(&K) -> V
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                match search_packed(occupied(pairs, *len), &key) {
+                    // `search_packed` only finds a key in an occupied slot.
+                    Ok(idx) => pairs[idx].as_mut().map(|&mut (_, ref mut value)| value)
+                                         .ok_or(CapacityError::new(key)),
+                    Err(_) if *len >= pairs.len() =>
+                        Err(CapacityError::new(key)),
+                    Err(idx) => {
+                        let value = f(&key);
+                        match shift_insert(pairs, *len, idx, (key, value)) {
+                            Ok(&mut (_, ref mut value)) => {
+                                *len += 1;
+                                Ok(value)
+                            }
+                            Err((key, _)) => Err(CapacityError::new(key))
+                        }
+                    }
+                }
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) =>
+                Ok(map.entry(key).or_insert_with_key(f))
         }
     }
 
-    #[test]
-    fn test_clear() {
-        let mut pairs = all_pairs_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        map.clear();
-        assert!(map.is_empty());
-        assert_eq!(map.len(), 0);
-        assert_eq!(unwrap(&map), all_pairs_empty());
+    /// Returns the entry for `key`, which is only converted into an owned key if it is not
+    /// present and a value is inserted for it.
+    ///
+    /// This avoids building an owned key, which may be expensive, for the occupied entry.
+    pub fn entry_ref<'m, 'q, Q>(&'m mut self, key: &'q Q) -> EntryRef<'m, 'q, K, V, Q>
+        where K: Borrow<Q> + From<&'q Q>, Q: Ord + ?Sized
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                // A free slot where the key was found only occurs in a corrupt map; it is
+                // treated as vacant, like a slot where the key is not present.
+                match search_packed(occupied(pairs, *len), key) {
+                    Ok(idx) => match pairs[idx] {
+                        Some((_, ref mut value)) => EntryRef::Occupied(value),
+                        None => EntryRef::Vacant(VacantEntryRef {
+                            inner: VacantInner::Borrowed { pairs, len, idx },
+                            key
+                        })
+                    },
+                    Err(idx) => EntryRef::Vacant(VacantEntryRef {
+                        inner: VacantInner::Borrowed { pairs, len, idx },
+                        key
+                    })
+                }
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => {
+                if map.contains_key(key) {
+                    EntryRef::Occupied(map.get_mut(key).unwrap())
+                } else {
+                    EntryRef::Vacant(VacantEntryRef { inner: VacantInner::Owned(map), key })
+                }
+            }
+        }
     }
 
-    #[test]
-    fn test_get_some() {
-        let mut pairs = all_pairs_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 4);
-        assert_eq!(map.get("a"), Some(&1));
-        assert_eq!(map.get("b"), Some(&2));
-        assert_eq!(map.get("c"), Some(&3));
-        assert_eq!(map.get("d"), Some(&4));
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) =>
+                remove_pair(pairs, len, key).ok(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => map.remove(key)
+        }
     }
 
-    #[test]
-    fn test_get_some_one_pair() {
-        let mut pairs = one_pair_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 1);
-        assert_eq!(map.get("a"), Some(&1));
+    /// Keeps only the first `len` entries in sorted order, removing the rest.
+    ///
+    /// This has no effect if the map has `len` or fewer entries. For an owned map, this
+    /// takes a single pass over the entries, since `BTreeMap` cannot be split at an index.
+    pub fn truncate(&mut self, len: usize) {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut old_len) => {
+                let entries = occupied_mut(pairs, *old_len);
+                if len < entries.len() {
+                    for item in entries[len..].iter_mut() {
+                        *item = None
+                    }
+                    *old_len = len
+                }
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => {
+                if len == 0 {
+                    map.clear()
+                } else if len < map.len() {
+                    let mut kept = 0;
+                    map.retain(|_, _| {
+                        kept += 1;
+                        kept <= len
+                    })
+                }
+            }
+        }
     }
 
-    #[test]
-    fn test_get_none_full() {
-        let mut pairs = all_pairs_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 4);
-        assert!(!map.is_empty());
-        assert_eq!(map.get("q"), None);
-        assert_eq!(map.get("0"), None);
+    /// Removes all entries with keys in `range`, returning how many were removed.
+    ///
+    /// For a borrowed map, the bounds of the range are binary searched and the entries
+    /// following it are shifted back in a single pass. For an owned map, the map is split
+    /// at the bounds of the range, and the entries following it are appended back, so removing
+    /// a prefix or a suffix of the map does not visit the other entries.
+    pub fn remove_range<Q, R>(&mut self, range: R) -> usize
+        where K: Borrow<Q>, Q: Ord + ?Sized, R: RangeBounds<Q>
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                let entries = occupied_mut(pairs, *len);
+                match binary_search_by_key_range(entries, range) {
+                    Ok((begin, end)) if begin < end => {
+                        for item in entries[begin..end].iter_mut() {
+                            *item = None
+                        }
+                        entries[begin..].rotate_left(end - begin);
+                        *len = entries.len() - (end - begin);
+                        end - begin
+                    },
+                    _ => 0
+                }
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => {
+                let mut removed = match range.start_bound() {
+                    Bound::Included(start) => map.split_off(start),
+                    Bound::Excluded(start) => {
+                        let mut removed = map.split_off(start);
+                        if let Some((key, value)) = removed.remove_entry(start) {
+                            map.insert(key, value);
+                        }
+                        removed
+                    },
+                    Bound::Unbounded => mem::take(map)
+                };
+                let mut rest = match range.end_bound() {
+                    Bound::Included(end) => {
+                        let mut rest = removed.split_off(end);
+                        if let Some((key, value)) = rest.remove_entry(end) {
+                            removed.insert(key, value);
+                        }
+                        rest
+                    },
+                    Bound::Excluded(end) => removed.split_off(end),
+                    Bound::Unbounded => BTreeMap::new()
+                };
+                map.append(&mut rest);
+                removed.len()
+            }
+        }
     }
 
-    #[test]
-    fn test_get_none() {
-        let mut pairs = one_pair_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 1);
-        assert!(!map.is_empty());
-        assert_eq!(map.get("0"), None);
-        assert_eq!(map.get("q"), None);
+    /// Returns an iterator that removes and yields the entries for which `pred` returns `true`,
+    /// in ascending key order.
+    ///
+    /// For a borrowed map, the entries that are kept are shifted back in the same single pass,
+    /// so the map stays packed and sorted. For an owned map, the entries are moved out of it,
+    /// and the ones that are kept are inserted back. If the iterator is dropped before it
+    /// is exhausted, the remaining entries are kept; if it is leaked instead, the contents
+    /// of the map are unspecified. If `pred` panics, the map is still left valid.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F>
+        where F: FnMut(&K, &mut V) -> bool
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                *len = occupied(pairs, *len).len();
+                ExtractIf(ExtractIfInner::Borrowed { pairs, len, read: 0, write: 0, pred })
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) =>
+                ExtractIf(ExtractIfInner::Owned {
+                    entries: mem::take(map).into_iter(), map, pred }),
+        }
     }
 
-    #[test]
-    fn test_get_none_empty() {
-        let mut pairs = all_pairs_empty();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 0);
-        assert!(map.is_empty());
-        assert_eq!(map.get("q"), None);
+    /// Like `get`, but reports why the lookup failed.
+    ///
+    /// For a borrowed map, the backing slice is verified to be sorted and packed, and to hold
+    /// as many entries as the stored length, first, which takes O(n) time; if it is not,
+    /// `Error::Corrupt` is returned.
+    pub fn checked_get<Q>(&self, key: &Q) -> Result<&V, Error>
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
+        match self {
+            ManagedMap::Borrowed(pairs, len) => {
+                if !is_sorted_and_packed(pairs, *len) {
+                    return Err(Error::Corrupt)
+                }
+                match pair_by_key(occupied(pairs, *len), key) {
+                    Ok((_, value)) => Ok(value),
+                    Err(_) => Err(Error::NotFound)
+                }
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(map) => map.get(key).ok_or(Error::NotFound)
+        }
     }
 
-    #[test]
-    fn test_range_full_unbounded() {
-        let mut pairs = all_pairs_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 4);
+    /// Like `insert`, but reports why the insertion failed.
+    ///
+    /// For a borrowed map, the backing slice is verified as with `checked_get` first,
+    /// which takes O(n) time; if it is not valid, the slice is left untouched and
+    /// an `InsertError` of kind `Error::Corrupt` is returned, holding the pair.
+    pub fn checked_insert(&mut self, key: K, new_value: V) -> Result<Option<V>, InsertError<K, V>> {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                if !is_sorted_and_packed(pairs, *len) {
+                    return Err(InsertError::new(Error::Corrupt, (key, new_value)))
+                }
+                insert_pair(pairs, len, key, new_value)
+                    .map(|(_idx, old_value)| old_value)
+                    .map_err(|(err, pair)| InsertError::new(err, pair))
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => Ok(map.insert(key, new_value))
+        }
+    }
 
-        let mut range = map.range("a"..);
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+    /// Like `remove`, but reports why the removal failed.
+    ///
+    /// For a borrowed map, the backing slice is verified as with `checked_get` first,
+    /// which takes O(n) time; if it is not valid, the slice is left untouched and
+    /// `Error::Corrupt` is returned.
+    pub fn checked_remove<Q>(&mut self, key: &Q) -> Result<V, Error>
+        where K: Borrow<Q>, Q: Ord + ?Sized
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                if !is_sorted_and_packed(pairs, *len) {
+                    return Err(Error::Corrupt)
+                }
+                remove_pair(pairs, len, key)
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => map.remove(key).ok_or(Error::NotFound)
+        }
+    }
 
-        let mut range = map.range("a"..);
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next_back(), Some((&"d", &4)));
-        assert_eq!(range.next_back(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next_back(), None);
-        assert_eq!(range.next(), None);
+    /// ManagedMap contains no elements?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-        let mut range = map.range("b"..);
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+    /// Returns the number of elements in the ManagedMap.
+    pub fn len(&self) -> usize {
+        match self {
+            ManagedMap::Borrowed(pairs, len) =>
+                occupied(pairs, *len).len(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(map) =>
+                map.len()
+        }
+    }
 
-        let mut range = map.range("d"..);
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+    /// Checks whether the map is borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            ManagedMap::Borrowed(..) => true,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(_) => false
+        }
+    }
 
-        let mut range = map.range(.."e");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+    /// Checks whether the map is owned.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
 
-        let mut range = map.range(.."d");
+    /// Checks whether the map upholds its invariant.
+    ///
+    /// For a borrowed map, the first `len` slots must be occupied and strictly sorted by key,
+    /// and the rest must be free. This can only be broken by constructing or modifying the
+    /// `Borrowed` variant directly, in which case other methods may misbehave. An owned map
+    /// is always valid.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            ManagedMap::Borrowed(pairs, len) =>
+                is_sorted_and_packed(pairs, *len),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(_) =>
+                true
+        }
+    }
+
+    /// Checks whether no more entries with new keys can be inserted.
+    ///
+    /// This is the case when every slot of a borrowed map is occupied; an owned map
+    /// is never full.
+    pub fn is_full(&self) -> bool {
+        match self {
+            ManagedMap::Borrowed(pairs, len) =>
+                *len >= pairs.len(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(_) =>
+                false
+        }
+    }
+
+    /// Returns an iterator over the entries of the map, in ascending key order.
+    ///
+    /// The order is the same for either variant, and does not depend on the order
+    /// in which the entries were inserted.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        match self {
+            ManagedMap::Borrowed(pairs, len) =>
+                Iter::Borrowed(occupied(pairs, *len).iter()),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(map) =>
+                Iter::Owned(map.iter()),
+        }
+    }
+
+    /// Returns an iterator over the entries of the map and their indices.
+    ///
+    /// For a borrowed map, the index is the position of the entry in the backing slice,
+    /// so that it can be used to look up a side table with the same layout. For an owned map,
+    /// it is the position of the entry in iteration order. Since the entries of a borrowed map
+    /// are packed, both are the same as the position that `get_index` takes.
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &K, &V)> {
+        self.iter().enumerate().map(|(idx, (key, value))| (idx, key, value))
+    }
+
+    /// Returns an iterator over the entries of the map, with mutable references to the values,
+    /// in ascending key order, like `iter`.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, len) =>
+                IterMut::Borrowed(occupied_mut(pairs, len).iter_mut()),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) =>
+                IterMut::Owned(map.iter_mut()),
+        }
+    }
+
+    /// Clears the map and refills it from an iterator of pairs sorted by key.
+    ///
+    /// Adjacent pairs with equal keys are deduplicated, with the last one winning.
+    /// For a borrowed map, sorted input is placed in a single pass without any shifting;
+    /// pairs that are out of order are still accepted, but are inserted one by one.
+    /// Returns the first pair that does not fit in a `CapacityError`; the pairs before it
+    /// remain in the map.
+    pub fn reload_sorted<I>(&mut self, iter: I) -> Result<(), CapacityError<(K, V)>>
+        where I: IntoIterator<Item = (K, V)>
+    {
+        self.clear();
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                for (key, value) in iter {
+                    if *len > 0 {
+                        match pairs[*len - 1] {
+                            Some((ref last_key, ref mut last_value)) if *last_key == key => {
+                                *last_value = value;
+                                continue
+                            }
+                            Some((ref last_key, _)) if *last_key > key => {
+                                match insert_pair(pairs, len, key, value) {
+                                    Ok(_) => (),
+                                    Err((_err, pair)) => return Err(CapacityError::new(pair))
+                                }
+                                continue
+                            }
+                            _ => ()
+                        }
+                    }
+                    if *len == pairs.len() {
+                        return Err(CapacityError::new((key, value)))
+                    }
+                    pairs[*len] = Some((key, value));
+                    *len += 1
+                }
+                Ok(())
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => {
+                map.extend(iter);
+                Ok(())
+            }
+        }
+    }
+
+    /// Merges pairs sorted by key into the map, replacing the values of existing keys.
+    ///
+    /// Adjacent pairs with equal keys are deduplicated, with the last one winning. For a
+    /// borrowed map, the entries are first moved to the back of the slice, and then merged
+    /// with sorted input in a single pass from the front, which takes O(n + m) time instead
+    /// of the O(n·m) time of inserting the pairs one by one. Pairs that are out of order
+    /// are still accepted, but they and the pairs after them are inserted one by one.
+    /// Returns the first pair that does not fit in a `CapacityError`; the pairs before it
+    /// remain in the map.
+    pub fn merge_sorted<I>(&mut self, iter: I) -> Result<(), CapacityError<(K, V)>>
+        where I: IntoIterator<Item = (K, V)>
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                *len = occupied(pairs, *len).len();
+                let capacity = pairs.len();
+                pairs.rotate_right(capacity - *len);
+                // The merged entries are written at `write`, and the entries yet to be merged
+                // start at `read`; the slots between them are free.
+                let (mut write, mut read) = (0, capacity - *len);
+                let mut iter = iter.into_iter();
+                let mut unsorted = None;
+                let mut result = Ok(());
+                for (key, value) in iter.by_ref() {
+                    if write > 0 {
+                        match pairs[write - 1] {
+                            Some((ref last_key, ref mut last_value)) if *last_key == key => {
+                                *last_value = value;
+                                continue
+                            }
+                            Some((ref last_key, _)) if *last_key > key => {
+                                unsorted = Some((key, value));
+                                break
+                            }
+                            _ => ()
+                        }
+                    }
+                    while read < capacity {
+                        match pairs[read] {
+                            Some((ref next_key, _)) if *next_key < key => (),
+                            _ => break
+                        }
+                        pairs[write] = pairs[read].take();
+                        write += 1;
+                        read += 1
+                    }
+                    let replaces = match pairs.get(read) {
+                        Some(&Some((ref next_key, _))) => *next_key == key,
+                        _ => false
+                    };
+                    if replaces {
+                        pairs[read] = None;
+                        read += 1
+                    } else if write == read {
+                        result = Err(CapacityError::new((key, value)));
+                        break
+                    }
+                    pairs[write] = Some((key, value));
+                    write += 1
+                }
+                pairs[write..].rotate_left(read - write);
+                *len = write + capacity - read;
+                if let Some(pair) = unsorted {
+                    for (key, value) in Some(pair).into_iter().chain(iter) {
+                        if let Err((_err, pair)) = insert_pair(pairs, len, key, value) {
+                            return Err(CapacityError::new(pair))
+                        }
+                    }
+                }
+                result
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => {
+                map.extend(iter);
+                Ok(())
+            }
+        }
+    }
+
+    /// Creates a borrowed map from unsorted pairs, using `scratch` as the backing slice.
+    ///
+    /// The pairs are sorted by key and packed at the front of `scratch`, and the rest of it
+    /// is cleared. Returns `Error::Full` if there are more pairs than `scratch` can hold,
+    /// and `Error::DuplicateKey` if two pairs have the same key; in both cases, the contents
+    /// of `scratch` are unspecified.
+    pub fn try_from_pairs_into<I>(pairs: I, scratch: &'a mut [Option<(K, V)>]) ->
+                                 Result<Self, Error>
+        where I: IntoIterator<Item = (K, V)>
+    {
+        let mut len = 0;
+        for pair in pairs {
+            if len == scratch.len() {
+                return Err(Error::Full)
+            }
+            scratch[len] = Some(pair);
+            len += 1
+        }
+        for item in scratch[len..].iter_mut() {
+            *item = None
+        }
+        sort_and_pack(&mut scratch[..len])?;
+        Ok(ManagedMap::Borrowed(scratch, len))
+    }
+
+    /// Creates a borrowed map from a slice whose entries are packed at the front in ascending
+    /// key order, checking that they are.
+    ///
+    /// Unlike `From<&mut [Option<(K, V)>]>`, this returns `Error::Corrupt` instead of a map
+    /// that would misbehave if the slice is not sorted and packed, which takes O(n) time.
+    /// This is useful for tables with a static lifetime, whose contents are not under
+    /// the control of the code that wraps them:
+    ///
+    /// ```rust
+    /// # use managed::ManagedMap;
+    /// static mut TABLE: [Option<(u8, u16)>; 4] = [Some((1, 10)), Some((2, 20)), None, None];
+    ///
+    /// // The only unsafe part is obtaining the reference, which must happen only once.
+    /// let table = unsafe { &mut *std::ptr::addr_of_mut!(TABLE) };
+    /// let mut map: ManagedMap<'static, u8, u16> = ManagedMap::try_from_sorted(table).unwrap();
+    /// map.insert(3, 30).unwrap();
+    /// assert_eq!(map.get(&2), Some(&20));
+    /// ```
+    pub fn try_from_sorted(pairs: &'a mut [Option<(K, V)>]) -> Result<Self, Error> {
+        let len = pairs.iter().take_while(|item| item.is_some()).count();
+        if !is_sorted_and_packed(pairs, len) {
+            return Err(Error::Corrupt)
+        }
+        Ok(ManagedMap::Borrowed(pairs, len))
+    }
+
+    /// Creates a borrowed map from a slice whose occupied slots may be in any order,
+    /// without scratch space.
+    ///
+    /// The occupied slots are sorted by key and packed at the front of the slice. Returns
+    /// `Error::DuplicateKey` if two of them have the same key; in that case, the slice is
+    /// still sorted and packed, but is not wrapped.
+    pub fn from_unsorted_in_place(pairs: &'a mut [Option<(K, V)>]) -> Result<Self, Error> {
+        sort_and_pack(pairs)?;
+        let len = pairs.iter().take_while(|item| item.is_some()).count();
+        Ok(ManagedMap::Borrowed(pairs, len))
+    }
+
+    /// Replaces every key with `f(key)`.
+    ///
+    /// `f` is expected to preserve the order of the keys, which is checked by a debug assertion.
+    /// If it does not, the entries are sorted again; if it maps several keys to the same key,
+    /// only one of their entries is kept, and which one is unspecified.
+    pub fn map_keys_in_place<F>(&mut self, mut f: F)
+        where F: FnMut(&K) -> K
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) => {
+                *len = occupied(pairs, *len).len();
+                for pair in occupied_mut(pairs, *len).iter_mut() {
+                    if let Some((ref mut key, _)) = *pair {
+                        *key = f(key)
+                    }
+                }
+                if is_sorted_and_packed(pairs, *len) {
+                    return
+                }
+                debug_assert!(false, "f must preserve the order of the keys");
+                let _ = sort_and_pack(occupied_mut(pairs, *len));
+                let mut kept = 0;
+                for idx in 0..*len {
+                    let key = pairs[idx].as_ref().map(|(key, _)| key);
+                    if kept > 0 && key == pairs[kept - 1].as_ref().map(|(key, _)| key) {
+                        pairs[idx] = None
+                    } else {
+                        pairs.swap(kept, idx);
+                        kept += 1
+                    }
+                }
+                *len = kept
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(ref mut map) => {
+                let mut monotonic = true;
+                for (key, value) in mem::take(map) {
+                    let key = f(&key);
+                    if let Some(last_key) = map.keys().next_back() {
+                        monotonic &= *last_key < key
+                    }
+                    map.insert(key, value);
+                }
+                debug_assert!(monotonic, "f must preserve the order of the keys");
+            }
+        }
+    }
+
+    /// Moves all entries of a borrowed map to the front of the backing slice.
+    ///
+    /// This restores packing after the backing slice was modified directly, keeping
+    /// the relative order of the entries, and recounts them; it does not sort them.
+    /// This is a no-op for an owned map.
+    pub fn compact(&mut self) {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, ref mut len) =>
+                *len = pack_options(pairs),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(_) => ()
+        }
+    }
+
+    /// Returns a borrowed map with the entries of the map.
+    ///
+    /// A borrowed map is simply reborrowed, and `scratch` is left untouched; changes made
+    /// through the result apply to the map. An owned map is instead copied into `scratch`,
+    /// as with `snapshot_into`, so the result is a detached copy: changes made through it
+    /// are lost, and do not apply to the map. Returns `Err(Error::Full)` if `scratch` cannot
+    /// hold every entry of the owned map.
+    // Without an owned variant, `scratch` is never needed.
+    #[cfg_attr(not(any(feature = "std", feature = "alloc")), allow(unused_variables))]
+    pub fn as_borrowed_into<'s>(&'s mut self, scratch: &'s mut [Option<(K, V)>]) ->
+                               Result<ManagedMap<'s, K, V>, Error>
+        where K: Clone, V: Clone
+    {
+        match self {
+            &mut ManagedMap::Borrowed(ref mut pairs, len) =>
+                Ok(ManagedMap::Borrowed(pairs, len)),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedMap::Owned(_) => self.snapshot_into(scratch)
+        }
+    }
+
+    /// Returns a borrowed copy of the map, stored in `scratch`.
+    ///
+    /// Unlike `as_borrowed_into`, this always copies the entries into `scratch` in sorted
+    /// order, for either variant, and the unused tail of `scratch` is cleared; the map
+    /// itself is left untouched, and can be used while the copy is alive.
+    /// Returns `Err(Error::Full)`, without modifying `scratch`, if it cannot hold every entry.
+    pub fn snapshot_into<'s>(&self, scratch: &'s mut [Option<(K, V)>]) ->
+                            Result<ManagedMap<'s, K, V>, Error>
+        where K: Clone, V: Clone
+    {
+        let len = self.len();
+        if len > scratch.len() {
+            return Err(Error::Full)
+        }
+        let mut entries = self.iter();
+        for slot in scratch.iter_mut() {
+            *slot = entries.next().map(|(key, value)| (key.clone(), value.clone()));
+        }
+        Ok(ManagedMap::Borrowed(scratch, len))
+    }
+}
+
+/// An error returned by `ManagedMap::serialize_packed` and `ManagedMap::deserialize_packed`.
+///
+/// This is only available with the `bytemuck` feature enabled.
+#[cfg(feature = "bytemuck")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackedError {
+    /// The map has more entries than fit in the `u32` prefix of the packed layout.
+    TooManyEntries,
+    /// The output buffer is too small to hold the packed entries.
+    BufferTooSmall,
+    /// The input ends before the last of the entries it declares.
+    Truncated,
+    /// The input holds more entries than the backing slice can hold.
+    Full,
+    /// The keys in the input are not in strictly ascending order.
+    Unsorted,
+}
+
+#[cfg(feature = "bytemuck")]
+impl fmt::Display for PackedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackedError::TooManyEntries => write!(f, "too many entries to pack"),
+            PackedError::BufferTooSmall => write!(f, "buffer too small"),
+            PackedError::Truncated => write!(f, "packed entries are truncated"),
+            PackedError::Full => write!(f, "map is full"),
+            PackedError::Unsorted => write!(f, "packed keys are not in ascending order"),
+        }
+    }
+}
+
+#[cfg(all(feature = "bytemuck", feature = "std"))]
+impl std::error::Error for PackedError {}
+
+/// Persistence of maps of plain-old-data types, without `serde` or allocation.
+///
+/// The packed layout is a little-endian `u32` holding the number of entries, followed by
+/// each entry in ascending key order, as the bytes of the key followed by the bytes of the
+/// value. The keys and values themselves are stored in native byte order, without padding,
+/// so it is the same for both variants, but not portable across platforms.
+///
+/// This is only available with the `bytemuck` feature enabled.
+#[cfg(feature = "bytemuck")]
+impl<'a, K: Ord + bytemuck::Pod + 'a, V: bytemuck::Pod + 'a> ManagedMap<'a, K, V> {
+    /// Writes the entries of the map to `out` in the packed layout, returning the number
+    /// of bytes written.
+    ///
+    /// Returns `Err(PackedError::BufferTooSmall)`, without writing anything, if `out`
+    /// is too small.
+    pub fn serialize_packed(&self, out: &mut [u8]) -> Result<usize, PackedError> {
+        let (key_size, value_size) = (mem::size_of::<K>(), mem::size_of::<V>());
+        if self.len() > u32::MAX as usize {
+            return Err(PackedError::TooManyEntries)
+        }
+        match self.len().checked_mul(key_size + value_size).and_then(|size| size.checked_add(4)) {
+            Some(size) if size <= out.len() => (),
+            _ => return Err(PackedError::BufferTooSmall)
+        }
+        out[..4].copy_from_slice(&(self.len() as u32).to_le_bytes());
+        let mut offset = 4;
+        for (key, value) in self.iter() {
+            out[offset..offset + key_size].copy_from_slice(bytemuck::bytes_of(key));
+            offset += key_size;
+            out[offset..offset + value_size].copy_from_slice(bytemuck::bytes_of(value));
+            offset += value_size;
+        }
+        Ok(offset)
+    }
+
+    /// Reads entries in the packed layout from `bytes` into a borrowed map backed by `pairs`.
+    ///
+    /// Any bytes after the entries are ignored. Returns `Err(PackedError::Truncated)` if
+    /// `bytes` is truncated, `Err(PackedError::Full)` if there are more entries than `pairs`
+    /// can hold, or `Err(PackedError::Unsorted)` if the keys are not in strictly ascending
+    /// order; in the last case, the contents of `pairs` are unspecified.
+    pub fn deserialize_packed(pairs: &'a mut [Option<(K, V)>], bytes: &[u8]) ->
+                             Result<Self, PackedError>
+    {
+        let (key_size, value_size) = (mem::size_of::<K>(), mem::size_of::<V>());
+        let mut prefix = [0; 4];
+        prefix.copy_from_slice(bytes.get(..4).ok_or(PackedError::Truncated)?);
+        let len = u32::from_le_bytes(prefix) as usize;
+        if len > pairs.len() {
+            return Err(PackedError::Full)
+        }
+        match len.checked_mul(key_size + value_size).and_then(|size| size.checked_add(4)) {
+            Some(size) if size <= bytes.len() => (),
+            _ => return Err(PackedError::Truncated)
+        }
+        let mut offset = 4;
+        for slot in pairs[..len].iter_mut() {
+            let key = bytemuck::pod_read_unaligned(&bytes[offset..offset + key_size]);
+            offset += key_size;
+            let value = bytemuck::pod_read_unaligned(&bytes[offset..offset + value_size]);
+            offset += value_size;
+            *slot = Some((key, value))
+        }
+        for slot in pairs[len..].iter_mut() {
+            *slot = None
+        }
+        if !is_sorted_and_packed(pairs, len) {
+            return Err(PackedError::Unsorted)
+        }
+        Ok(ManagedMap::Borrowed(pairs, len))
+    }
+}
+
+pub enum Iter<'a, K: 'a, V: 'a> {
+    /// Borrowed variant.
+    Borrowed(slice::Iter<'a, Option<(K, V)>>),
+    /// Owned variant, only available with the `std` or `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Owned(BTreeIter<'a, K, V>),
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            &mut Iter::Borrowed(ref mut iter) =>
+                match iter.next() {
+                    Some(&Some((ref k, ref v))) => Some((k, v)),
+                    Some(&None) => None,
+                    None => None,
+                },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut Iter::Owned(ref mut iter) =>
+                iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Iter::Borrowed(iter) =>
+                iter.size_hint(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            Iter::Owned(iter) =>
+                iter.size_hint(),
+        }
+    }
+}
+
+pub enum IterMut<'a, K: 'a, V: 'a> {
+    /// Borrowed variant.
+    Borrowed(slice::IterMut<'a, Option<(K, V)>>),
+    /// Owned variant, only available with the `std` or `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Owned(BTreeIterMut<'a, K, V>),
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            &mut IterMut::Borrowed(ref mut iter) =>
+                match iter.next() {
+                    Some(&mut Some((ref k, ref mut v))) => Some((k, v)),
+                    Some(&mut None) => None,
+                    None => None,
+                },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut IterMut::Owned(ref mut iter) =>
+                iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            IterMut::Borrowed(iter) => {
+                let (_, upper) = iter.size_hint();
+                (0, upper)
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            IterMut::Owned(iter) =>
+                iter.size_hint(),
+        }
+    }
+}
+
+pub enum IntoIter<'a, K: 'a, V: 'a> {
+    /// Borrowed variant, holding the occupied slots of the backing slice and the index
+    /// of the next pair to take out of them.
+    Borrowed {
+        pairs: &'a mut [Option<(K, V)>],
+        read:  usize,
+    },
+    /// Owned variant, only available with the `std` or `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Owned(BTreeIntoIter<K, V>),
+}
+
+impl<'a, K: 'a, V: 'a> Iterator for IntoIter<'a, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            &mut IntoIter::Borrowed { ref mut pairs, ref mut read } =>
+                match pairs.get_mut(*read) {
+                    Some(item) => {
+                        *read += 1;
+                        item.take()
+                    },
+                    None => None,
+                },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut IntoIter::Owned(ref mut iter) =>
+                iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            IntoIter::Borrowed { pairs, read } =>
+                (0, Some(pairs.len() - read)),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            IntoIter::Owned(iter) =>
+                iter.size_hint(),
+        }
+    }
+}
+
+impl<'a, K: 'a, V: 'a> Drop for IntoIter<'a, K, V> {
+    fn drop(&mut self) {
+        match *self {
+            IntoIter::Borrowed { ref mut pairs, read } =>
+                pairs.rotate_left(read),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            IntoIter::Owned(_) => ()
+        }
+    }
+}
+
+/// Copies the pairs into the map, replacing the values of keys that are already present.
+///
+/// Pairs with new keys that do not fit into a full borrowed map are skipped; use `insert_many`
+/// to find out how many pairs were inserted.
+impl<'a, 'b, K: Ord + Copy + 'a, V: Copy + 'a> Extend<&'b (K, V)> for ManagedMap<'a, K, V> {
+    fn extend<I: IntoIterator<Item = &'b (K, V)>>(&mut self, iter: I) {
+        for &(key, value) in iter {
+            let _ = self.insert(key, value);
+        }
+    }
+}
+
+/// Consumes the map, yielding the pairs in ascending key order.
+///
+/// For a borrowed map, each yielded pair is taken out of the backing slice,
+/// which is left empty once the iterator is exhausted. If the iterator is dropped before
+/// then, the remaining pairs are moved back to the front of the slice, so that it can be
+/// made into a map again.
+impl<'a, K: 'a, V: 'a> IntoIterator for ManagedMap<'a, K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            ManagedMap::Borrowed(pairs, len) =>
+                IntoIter::Borrowed { pairs: occupied_mut(pairs, len), read: 0 },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedMap::Owned(map) =>
+                IntoIter::Owned(map.into_iter()),
+        }
+    }
+}
+
+enum ExtractIfInner<'m, K: Ord + 'm, V: 'm, F> {
+    Borrowed {
+        pairs: &'m mut [Option<(K, V)>],
+        len:   &'m mut usize,
+        read:  usize,
+        write: usize,
+        pred:  F,
+    },
+    /// The entries are moved out of the map, and the ones that are kept are inserted back
+    /// in ascending key order.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Owned {
+        map:     &'m mut BTreeMap<K, V>,
+        entries: BTreeIntoIter<K, V>,
+        pred:    F,
+    },
+}
+
+/// An iterator that removes and yields the entries of a map matching a predicate,
+/// returned by `ManagedMap::extract_if`.
+pub struct ExtractIf<'m, K: Ord + 'm, V: 'm, F>(ExtractIfInner<'m, K, V, F>);
+
+impl<'m, K: Ord + 'm, V: 'm, F> Iterator for ExtractIf<'m, K, V, F>
+        where F: FnMut(&K, &mut V) -> bool {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0 {
+            ExtractIfInner::Borrowed { ref mut pairs, ref len, ref mut read, ref mut write,
+                                       ref mut pred } => {
+                while *read < **len {
+                    let idx = *read;
+                    let extract = match pairs[idx] {
+                        Some((ref key, ref mut value)) => pred(key, value),
+                        None => false
+                    };
+                    // Only advance once `pred` returns, so that the pair is kept if it panics.
+                    *read += 1;
+                    if extract {
+                        return pairs[idx].take()
+                    }
+                    pairs.swap(*write, idx);
+                    *write += 1;
+                }
+                None
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ExtractIfInner::Owned { ref mut map, ref mut entries, ref mut pred } => {
+                for (key, mut value) in entries.by_ref() {
+                    if pred(&key, &mut value) {
+                        return Some((key, value))
+                    }
+                    map.insert(key, value);
+                }
+                None
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0 {
+            ExtractIfInner::Borrowed { ref len, read, .. } =>
+                (0, Some(**len - read)),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ExtractIfInner::Owned { ref entries, .. } =>
+                (0, entries.size_hint().1),
+        }
+    }
+}
+
+impl<'m, K: Ord + 'm, V: 'm, F> Drop for ExtractIfInner<'m, K, V, F> {
+    fn drop(&mut self) {
+        match *self {
+            ExtractIfInner::Borrowed { ref mut pairs, ref mut len, read, ref mut write, .. } => {
+                for idx in read..**len {
+                    pairs.swap(*write, idx);
+                    *write += 1;
+                }
+                **len = *write;
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ExtractIfInner::Owned { ref mut map, ref mut entries, .. } =>
+                map.extend(entries)
+        }
+    }
+}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::{ManagedMap, EntryRef, Error, InsertError, OccupiedError, TryInsertError,
+                shift_insert, shift_remove};
+    use core::borrow::Borrow;
+    use core::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use error::CapacityError;
+    use slice::ManagedSlice;
+    use core::cmp::Ordering;
+    use core::ops::Bound::*;
+    #[cfg(feature = "bytemuck")]
+    use super::PackedError;
+    #[cfg(feature = "std")]
+    use std::collections::BTreeMap;
+    #[cfg(feature = "std")]
+    use std::format;
+    #[cfg(feature = "std")]
+    use std::collections::hash_map::DefaultHasher;
+    #[cfg(feature = "std")]
+    use core::hash::{Hash, Hasher};
+
+    #[cfg(feature = "std")]
+    fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn all_pairs_empty() -> [Option<(&'static str, u32)>; 4] {
+        [None; 4]
+    }
+
+    fn one_pair_full() -> [Option<(&'static str, u32)>; 4] {
+        [Some(("a", 1)), None, None, None]
+    }
+
+    fn all_pairs_full() -> [Option<(&'static str, u32)>; 4] {
+        [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), Some(("d", 4))]
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct CaseInsensitive(&'static str);
+
+    impl Ord for CaseInsensitive {
+        fn cmp(&self, other: &Self) -> Ordering {
+            let lhs = self.0.bytes().map(|c| c.to_ascii_lowercase());
+            let rhs = other.0.bytes().map(|c| c.to_ascii_lowercase());
+            lhs.cmp(rhs)
+        }
+    }
+
+    impl PartialOrd for CaseInsensitive {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl PartialEq for CaseInsensitive {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for CaseInsensitive {}
+
+    fn unwrap<'a, K, V>(map: &'a ManagedMap<'a, K, V>) -> &'a [Option<(K, V)>] {
+        match map {
+            ManagedMap::Borrowed(map, _) => map,
+            #[allow(unreachable_patterns)]
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_debug() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), None];
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(format!("{:?}", map), r#"{"a": 1, "b": 2}"#);
+        let layout = format!("{:#?}", map);
+        assert!(layout.starts_with("Borrowed("));
+        assert!(layout.contains("None"));
+
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(format!("{:?}", map), "{}");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_debug_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("b", 2).unwrap();
+        map.insert("a", 1).unwrap();
+        assert_eq!(format!("{:?}", map), r#"{"a": 1, "b": 2}"#);
+        assert_eq!(format!("{:#?}", map), "{\n    \"a\": 1,\n    \"b\": 2,\n}");
+    }
+
+    #[test]
+    fn test_cmp_borrowed() {
+        let mut pairs_a = [Some(("a", 1)), Some(("b", 2)), None, None];
+        let mut pairs_b = [Some(("a", 1)), Some(("b", 2)), None];
+        let mut pairs_c = [Some(("a", 1)), Some(("b", 3)), None, None];
+        let a = ManagedMap::from(&mut pairs_a[..]);
+        let b = ManagedMap::from(&mut pairs_b[..]);
+        let c = ManagedMap::from(&mut pairs_c[..]);
+        assert!(a == b);
+        assert!(a != c);
+        assert!(a < c);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_cmp_hash_cross_variant() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), None, None];
+        let borrowed = ManagedMap::from(&mut pairs[..]);
+        let btree: BTreeMap<_, _> = [("a", 1), ("b", 2)].iter().cloned().collect();
+        let owned = ManagedMap::Owned(btree.clone());
+        let shorter = ManagedMap::Owned([("a", 1)].iter().cloned().collect());
+        assert!(borrowed == owned);
+        assert_eq!(borrowed.cmp(&owned), Ordering::Equal);
+        assert!(shorter < borrowed);
+        assert_eq!(hash_of(&borrowed), hash_of(&owned));
+        assert_eq!(hash_of(&borrowed), hash_of(&btree));
+    }
+
+    #[test]
+    fn test_shift_insert() {
+        let mut slots = [Some(1), Some(3), None];
+        assert_eq!(shift_insert(&mut slots, 2, 1, 2), Ok(&mut 2));
+        assert_eq!(slots, [Some(1), Some(2), Some(3)]);
+        assert_eq!(shift_insert(&mut slots, 3, 0, 0), Err(0));
+
+        let mut slots = [None, None];
+        assert_eq!(shift_insert(&mut slots, 0, 0, 1), Ok(&mut 1));
+        assert_eq!(shift_insert(&mut slots, 1, 1, 2), Ok(&mut 2));
+        assert_eq!(slots, [Some(1), Some(2)]);
+
+        let mut slots = [None, None];
+        assert_eq!(shift_insert(&mut slots, 0, 1, 1), Err(1));
+        assert_eq!(slots, [None, None]);
+
+        let mut slots: [Option<u8>; 0] = [];
+        assert_eq!(shift_insert(&mut slots, 0, 0, 1), Err(1));
+    }
+
+    #[test]
+    fn test_shift_remove() {
+        let mut slots = [Some(1), Some(2), Some(3)];
+        assert_eq!(shift_remove(&mut slots, 3, 2), Some(3));
+        assert_eq!(slots, [Some(1), Some(2), None]);
+        assert_eq!(shift_remove(&mut slots, 2, 0), Some(1));
+        assert_eq!(slots, [Some(2), None, None]);
+        assert_eq!(shift_remove(&mut slots, 1, 1), None);
+        assert_eq!(shift_remove(&mut slots, 1, 0), Some(2));
+        assert_eq!(slots, [None, None, None]);
+        assert_eq!(shift_remove(&mut slots, 0, 0), None);
+        assert_eq!(shift_remove(&mut slots, 4, 0), None);
+
+        let mut slots: [Option<u8>; 0] = [];
+        assert_eq!(shift_remove(&mut slots, 0, 0), None);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+        assert_eq!(unwrap(&map), all_pairs_empty());
+    }
+
+    #[test]
+    fn test_clear_partial() {
+        let mut pairs = [None; 1024];
+        pairs[0] = Some((0, 0));
+        pairs[1] = Some((1, 1));
+        pairs[2] = Some((2, 2));
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.clear();
+        assert!(map.is_empty());
+        assert!(unwrap(&map).iter().all(|item| item.is_none()));
+        assert_eq!(map.insert(5, 5), Ok(None));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_clear_copy() {
+        let mut pairs = [Some((1u8, 1u32)), Some((2, 2)), None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(unwrap(&map),       [None; 4]);
+    }
+
+    #[test]
+    fn test_get_or() {
+        let mut pairs = one_pair_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.get_or("a", &0), &1);
+        assert_eq!(map.get_or("b", &0), &0);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_get_mut_or() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        let mut scratch = 0;
+        *map.get_mut_or("a", &mut scratch) += 10;
+        *map.get_mut_or("b", &mut scratch) += 5;
+        assert_eq!(scratch, 5);
+        assert_eq!(map.get("a"), Some(&11));
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_or_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("a", 1).unwrap();
+        assert_eq!(map.get_or("a", &0), &1);
+        assert_eq!(map.get_or("b", &0), &0);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_stored_len() {
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::Borrowed(&mut pairs, 0);
+        assert!(map.is_empty());
+        assert!(!map.is_full());
+        for (idx, key) in ["d", "b", "a", "c"].iter().enumerate() {
+            assert_eq!(map.len(), idx);
+            assert_eq!(map.insert(key, idx as u32), Ok(None));
+        }
+        assert_eq!(map.len(), 4);
+        assert!(map.is_full());
+        assert_eq!(map.remove("b"), Some(1));
+        assert_eq!(map.len(), 3);
+        assert!(!map.is_full());
+        match map {
+            ManagedMap::Borrowed(_, len) => assert_eq!(len, 3),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_vec_backed_like_owned() {
+        let mut pairs = [None; 8].to_vec();
+        let mut borrowed = ManagedMap::from(&mut pairs[..]);
+        let mut owned = ManagedMap::Owned(BTreeMap::new());
+        for map in [&mut borrowed, &mut owned].iter_mut() {
+            assert_eq!(map.insert("c", 3), Ok(None));
+            assert_eq!(map.insert("a", 1), Ok(None));
+            assert_eq!(map.insert("b", 2), Ok(None));
+            assert_eq!(map.insert("a", 10), Ok(Some(1)));
+            assert_eq!(map.remove("b"), Some(2));
+            assert_eq!(map.remove("b"), None);
+        }
+        assert_eq!(borrowed.len(), owned.len());
+        assert!(borrowed.iter().eq(owned.iter()));
+        assert!(borrowed.range("b"..).eq(owned.range("b"..)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_is_full_owned() {
+        let map: ManagedMap<&str, u32> = ManagedMap::Owned(BTreeMap::new());
+        assert!(!map.is_full());
+    }
+
+    #[test]
+    fn test_get_some() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.get("c"), Some(&3));
+        assert_eq!(map.get("d"), Some(&4));
+    }
+
+    #[test]
+    fn test_get_some_one_pair() {
+        let mut pairs = one_pair_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("a"), Some(&1));
+    }
+
+    #[test]
+    fn test_get_none_full() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 4);
+        assert!(!map.is_empty());
+        assert_eq!(map.get("q"), None);
+        assert_eq!(map.get("0"), None);
+    }
+
+    #[test]
+    fn test_get_none() {
+        let mut pairs = one_pair_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 1);
+        assert!(!map.is_empty());
+        assert_eq!(map.get("0"), None);
+        assert_eq!(map.get("q"), None);
+    }
+
+    #[test]
+    fn test_get_none_empty() {
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 0);
+        assert!(map.is_empty());
+        assert_eq!(map.get("q"), None);
+    }
+
+    #[test]
+    fn test_range_full_unbounded() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 4);
+
+        let mut range = map.range("a"..);
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+
+        let mut range = map.range("a"..);
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next_back(), Some((&"d", &4)));
+        assert_eq!(range.next_back(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next_back(), None);
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("b"..);
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+
+        let mut range = map.range("d"..);
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+
+        let mut range = map.range(.."e");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+
+        let mut range = map.range(.."d");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+
+        let mut range = map.range(.."b");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+
+        let mut range = map.range(.."a");
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+
+        let mut range = map.range::<&str, _>(..);
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+    }
+
+    #[test]
+    fn test_range_full_exclude_left() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 4);
+
+        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("a")));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("b")));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("c")));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("d")));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("e")));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+    }
+
+    #[test]
+    fn test_range_full_include_right() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 4);
+
+        let mut range = map.range::<&str, _>((Included("b"), Included("a")));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("b")));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("c")));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("d")));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("e")));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range::<&str, _>((Included("b"), Included("a")));
+        assert_eq!(range.next_back(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("b")));
+        assert_eq!(range.next_back(), Some((&"b", &2)));
+        assert_eq!(range.next_back(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("c")));
+        assert_eq!(range.next_back(), Some((&"c", &3)));
+        assert_eq!(range.next_back(), Some((&"b", &2)));
+        assert_eq!(range.next_back(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("d")));
+        assert_eq!(range.next_back(), Some((&"d", &4)));
+        assert_eq!(range.next_back(), Some((&"c", &3)));
+        assert_eq!(range.next_back(), Some((&"b", &2)));
+        assert_eq!(range.next_back(), None);
+        let mut range = map.range::<&str, _>((Included("b"), Included("e")));
+        assert_eq!(range.next_back(), Some((&"d", &4)));
+        assert_eq!(range.next_back(), Some((&"c", &3)));
+        assert_eq!(range.next_back(), Some((&"b", &2)));
+        assert_eq!(range.next_back(), None);
+    }
+
+    #[test]
+    fn test_range_full() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 4);
+
+        let mut range = map.range("0".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("0".."b");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("0".."c");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("0".."d");
         assert_eq!(range.next(), Some((&"a", &1)));
         assert_eq!(range.next(), Some((&"b", &2)));
         assert_eq!(range.next(), Some((&"c", &3)));
         assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+        let mut range = map.range("0".."e");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("a".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("a".."b");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("a".."c");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("a".."d");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("a".."e");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("b".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."b");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."c");
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."d");
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."e");
+        assert_eq!(range.next(), Some((&"b", &2)));
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("c".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("c".."b");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("c".."c");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("c".."d");
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("c".."e");
+        assert_eq!(range.next(), Some((&"c", &3)));
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("d".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("d".."b");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("d".."c");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("d".."d");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("d".."e");
+        assert_eq!(range.next(), Some((&"d", &4)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("e".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("e".."b");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("e".."c");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("e".."d");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("e".."e");
+        assert_eq!(range.next(), None);
+    }
+
+    #[test]
+    fn test_range_one_pair() {
+        let mut pairs = one_pair_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 1);
+
+        let mut range = map.range("0".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("0".."b");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("0".."c");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("a".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("a".."b");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), None);
+        let mut range = map.range("a".."c");
+        assert_eq!(range.next(), Some((&"a", &1)));
+        assert_eq!(range.next(), None);
+
+        let mut range = map.range("b".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."b");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."c");
+        assert_eq!(range.next(), None);
+    }
+
+    #[test]
+    fn test_range_empty() {
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 0);
+
+        let mut range = map.range("b".."a");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."b");
+        assert_eq!(range.next(), None);
+        let mut range = map.range("b".."c");
+        assert_eq!(range.next(), None);
+    }
+
+    #[test]
+    fn test_get_mut_some() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 4);
+        assert!(!map.is_empty());
+        assert_eq!(map.get_mut("a"), Some(&mut 1));
+        assert_eq!(map.get_mut("b"), Some(&mut 2));
+        assert_eq!(map.get_mut("c"), Some(&mut 3));
+        assert_eq!(map.get_mut("d"), Some(&mut 4));
+    }
+
+    #[test]
+    fn test_get_mut_none() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.get_mut("q"), None);
+    }
+
+    #[test]
+    fn test_get_or_insert_with_key() {
+        let mut pairs = [Some(("a", 1)), None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.get_or_insert_with_key("a", |_| unreachable!()), Some(&mut 1));
+        assert_eq!(map.get_or_insert_with_key("abc", |key| key.len() as u32), Some(&mut 3));
+        assert_eq!(map.get("abc"), Some(&3));
+        assert_eq!(map.get_or_insert_with_key("b", |_| unreachable!()), None);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("abc", 3))]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_or_insert_with_key_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.get_or_insert_with_key("ab", |key| key.len() as u32), Some(&mut 2));
+        assert_eq!(map.get_or_insert_with_key("ab", |_| unreachable!()), Some(&mut 2));
+        assert_eq!(map.get("ab"), Some(&2));
+    }
+
+    #[test]
+    fn test_index() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map["b"], 2);
+        map["b"] = 20;
+        map["d"] += 1;
+        assert_eq!(map["b"], 20);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 20)), Some(("c", 3)), Some(("d", 5))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "no entry found for key")]
+    fn test_index_missing() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        let _ = map["e"];
+    }
+
+    #[test]
+    #[should_panic(expected = "no entry found for key")]
+    fn test_index_mut_missing() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map["e"] = 5;
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_index_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("a", 1).unwrap();
+        map["a"] = 10;
+        assert_eq!(map["a"], 10);
+    }
+
+    /// A key that counts how many times it was built from a `&str` or cloned.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct CountedKey(&'static str);
+
+    static COUNTED_KEYS: AtomicUsize = AtomicUsize::new(0);
+
+    impl Clone for CountedKey {
+        fn clone(&self) -> Self {
+            COUNTED_KEYS.fetch_add(1, AtomicOrdering::SeqCst);
+            CountedKey(self.0)
+        }
+    }
+
+    impl<'a> From<&'a str> for CountedKey {
+        fn from(key: &'a str) -> Self {
+            COUNTED_KEYS.fetch_add(1, AtomicOrdering::SeqCst);
+            // Only keys that outlive the test are looked up.
+            match key {
+                "a" => CountedKey("a"),
+                "b" => CountedKey("b"),
+                "c" => CountedKey("c"),
+                _ => unreachable!()
+            }
+        }
+    }
+
+    impl Borrow<str> for CountedKey {
+        fn borrow(&self) -> &str {
+            self.0
+        }
+    }
+
+    fn check_entry_ref(map: &mut ManagedMap<CountedKey, u32>) {
+        let before = COUNTED_KEYS.load(AtomicOrdering::SeqCst);
+        match map.entry_ref("a") {
+            EntryRef::Occupied(value) => *value += 1,
+            EntryRef::Vacant(_) => unreachable!()
+        }
+        assert_eq!(map.entry_ref("a").or_insert(0), Ok(&mut 2));
+        assert_eq!(COUNTED_KEYS.load(AtomicOrdering::SeqCst), before);
+
+        match map.entry_ref("b") {
+            EntryRef::Occupied(_) => unreachable!(),
+            EntryRef::Vacant(entry) => {
+                assert_eq!(entry.key(), "b");
+                assert_eq!(entry.insert(3), Ok(&mut 3));
+            }
+        }
+        assert_eq!(COUNTED_KEYS.load(AtomicOrdering::SeqCst), before + 1);
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&3));
+    }
+
+    #[test]
+    fn test_entry_ref_corrupt() {
+        let mut pairs = [Some(("a", 1)), None, Some(("c", 3)), None];
+        let mut map = ManagedMap::Borrowed(&mut pairs[..], 3);
+        assert!(!map.is_valid());
+        assert_eq!(map.entry_ref("a").or_insert(0), Ok(&mut 1));
+        assert!(map.entry_ref("c").or_insert(0).is_ok());
+    }
+
+    #[test]
+    fn test_entry_ref() {
+        let mut pairs = [Some((CountedKey("a"), 1)), None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        check_entry_ref(&mut map);
+        assert!(map.is_valid());
+        assert_eq!(map.entry_ref("c").or_insert_with(|| 4), Ok(&mut 4));
+
+        // The key is not built when there is no room for it.
+        let mut pairs = [Some((CountedKey("a"), 1))];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.entry_ref("b").or_insert(2), Err(CapacityError::new(2)));
+        assert_eq!(map.len(), 1);
+
+        // This is part of the same test, as the counter is shared by the whole test binary.
+        #[cfg(feature = "std")]
+        {
+            let mut map = ManagedMap::Owned(BTreeMap::new());
+            map.insert(CountedKey("a"), 1).unwrap();
+            check_entry_ref(&mut map);
+            assert_eq!(map.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let mut pairs = [Some(("b", 2)), None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.try_insert("a", 1).ok(), Some(&mut 1));
+        match map.try_insert("b", 20) {
+            Err(TryInsertError::Occupied(OccupiedError { key, value, existing })) => {
+                assert_eq!((key, value), ("b", 20));
+                *existing += 1;
+            }
+            _ => unreachable!()
+        }
+        assert_eq!(map.get("b"), Some(&3));
+        assert_eq!(map.try_insert("c", 3).ok(), Some(&mut 3));
+        match map.try_insert("d", 4) {
+            Err(TryInsertError::Full(err)) => assert_eq!(err.into_inner(), ("d", 4)),
+            _ => unreachable!()
+        }
+        match map.try_insert("a", 10) {
+            Err(TryInsertError::Occupied(err)) => assert_eq!(*err.existing, 1),
+            _ => unreachable!()
+        }
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 3)), Some(("c", 3))]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_try_insert_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.try_insert("a", 1).ok(), Some(&mut 1));
+        match map.try_insert("a", 10) {
+            Err(err) => assert_eq!(format!("{}", err),
+                                   "failed to insert 10, key \"a\" already exists with value 1"),
+            _ => unreachable!()
+        }
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.len(), 1);
+        let err: TryInsertError<&str, u32> = TryInsertError::Corrupt(("b", 2));
+        assert_eq!(format!("{}", err), "map is not sorted and packed");
+    }
+
+    #[test]
+    fn test_get_pair_mut() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        {
+            let (key, value) = map.get_pair_mut("b").unwrap();
+            assert_eq!(*key, "b");
+            *value += key.len() as u32;
+        }
+        assert_eq!(map.get_pair_mut("e"), None);
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 3)), Some(("c", 3)), Some(("d", 4))]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_pair_mut_owned() {
+        let mut map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        {
+            let (key, value) = map.get_pair_mut("c").unwrap();
+            assert_eq!(*key, "c");
+            *value = 30;
+        }
+        assert_eq!(map.get_pair_mut("e"), None);
+        assert_eq!(map.get_pair_mut(""), None);
+        assert_eq!(map.get("c"), Some(&30));
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_length_past_end() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2))];
+        let mut map = ManagedMap::Borrowed(&mut pairs[..], 5);
+        assert_eq!(format!("{:?}", map), r#"{"a": 1, "b": 2}"#);
+        assert!(!map.is_valid());
+        assert!(map.is_full());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.get_mut("c"), None);
+        assert_eq!(map.get_index(1), Some((&"b", &2)));
+        assert_eq!(map.iter().count(), 2);
+        assert_eq!(map.iter_mut().count(), 2);
+        assert_eq!(map.range("a"..).count(), 2);
+        assert_eq!(map.iter_split_at("b").1.count(), 1);
+        assert!(map.insert("c", 3).is_err());
+        assert_eq!(map.remove_range("b"..), 1);
+        assert_eq!(map.len(), 1);
+
+        let mut pairs = [Some(("a", 1)), Some(("b", 2))];
+        let mut map = ManagedMap::Borrowed(&mut pairs[..], 5);
+        map.truncate(1);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), None]);
+
+        let mut pairs = [Some(("a", 1)), Some(("b", 2))];
+        let mut map = ManagedMap::Borrowed(&mut pairs[..], 5);
+        assert_eq!(map.extract_if(|key, _| *key == "a").next(), Some(("a", 1)));
+        assert_eq!(unwrap(&map), [Some(("b", 2)), None]);
+
+        let mut pairs = [Some(("a", 1)), Some(("b", 2))];
+        let mut map = ManagedMap::Borrowed(&mut pairs[..], 5);
+        assert_eq!(map.merge_sorted([("c", 3)].iter().cloned()).map_err(|err| err.into_inner()),
+                   Err(("c", 3)));
+        map.map_keys_in_place(|key| *key);
+        assert!(map.into_iter().eq([("a", 1), ("b", 2)].iter().cloned()));
+
+        let mut pairs = [Some(("a", 1)), Some(("b", 2))];
+        let mut map = ManagedMap::Borrowed(&mut pairs[..], 5);
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_contains_value() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), None, Some(("z", 26))];
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert!(map.contains_value(&1));
+        assert!(map.contains_value(&2));
+        assert!(!map.contains_value(&3));
+        // Stale entries past the length of the map are not considered.
+        let mut pairs = [Some(("a", 1)), None, Some(("z", 26))];
+        let map = ManagedMap::Borrowed(&mut pairs[..], 1);
+        assert!(!map.contains_value(&26));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_contains_value_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert!(!map.contains_value(&1));
+        map.insert("a", 1).unwrap();
+        map.insert("b", 1).unwrap();
+        assert!(map.contains_value(&1));
+        assert!(!map.contains_value(&2));
+    }
+
+    /// Inserts `keys` into `map` in a pseudo-random order determined by `seed`.
+    fn insert_shuffled(map: &mut ManagedMap<u32, u32>, keys: &mut [u32], mut seed: u32) {
+        for idx in (1..keys.len()).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            keys.swap(idx, seed as usize % (idx + 1));
+        }
+        for &key in keys.iter() {
+            assert_eq!(map.insert(key, key * 10), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_iter_order_independent_of_insertion() {
+        let mut first = [None; 16];
+        let mut first = ManagedMap::from(&mut first[..]);
+        insert_shuffled(&mut first, &mut [9, 3, 14, 0, 7, 12, 5, 1, 15, 8], 1);
+        for seed in 2..10 {
+            let mut pairs = [None; 16];
+            let mut map = ManagedMap::from(&mut pairs[..]);
+            insert_shuffled(&mut map, &mut [0, 1, 3, 5, 7, 8, 9, 12, 14, 15], seed);
+            assert!(map.iter().eq(first.iter()));
+            assert!(map.iter().zip(map.iter().skip(1)).all(|((lhs, _), (rhs, _))| lhs < rhs));
+            assert!(map.range(5..).eq(first.iter().skip(3)));
+            let copied = |(key, value): (&u32, &u32)| (*key, *value);
+            assert!(map.iter_mut().map(|(key, value)| (*key, *value)).eq(first.iter().map(copied)));
+            assert!(map.into_iter().eq(first.iter().map(copied)));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_iter_order_borrowed_matches_owned() {
+        use std::vec::Vec;
+
+        let mut expected = None;
+        for seed in 1..10 {
+            let mut keys = [42, 7, 1000, 3, 99, 512, 0, 64, 8, 21, 5, 777];
+            let mut pairs = [None; 12];
+            let mut borrowed = ManagedMap::from(&mut pairs[..]);
+            insert_shuffled(&mut borrowed, &mut keys, seed);
+            let mut owned = ManagedMap::Owned(BTreeMap::new());
+            insert_shuffled(&mut owned, &mut keys, seed ^ 0xfeed);
+            // Removing and reinserting does not change the order either.
+            for map in [&mut borrowed, &mut owned].iter_mut() {
+                assert_eq!(map.remove(&99), Some(990));
+                assert_eq!(map.remove(&0), Some(0));
+                assert_eq!(map.insert(99, 990), Ok(None));
+                assert_eq!(map.insert(0, 0), Ok(None));
+            }
+
+            let from_borrowed: Vec<(u32, u32)> =
+                borrowed.iter().map(|(key, value)| (*key, *value)).collect();
+            let from_owned: Vec<(u32, u32)> =
+                owned.iter().map(|(key, value)| (*key, *value)).collect();
+            assert_eq!(from_borrowed, from_owned);
+            assert!(from_borrowed.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            assert_eq!(format!("{:?}", borrowed), format!("{:?}", owned));
+            match expected {
+                Some(ref expected) => assert_eq!(&from_borrowed, expected),
+                None => expected = Some(from_borrowed)
+            }
+            assert!(borrowed.into_iter().eq(owned.into_iter()));
+        }
+    }
+
+    #[test]
+    fn test_get_each() {
+        let mut pairs = [Some(("a", 1)), Some(("c", 3)), None, None];
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut values = map.get_each(["c", "b", "a", "d"].iter().cloned());
+        assert_eq!(values.next(), Some(Some(&3)));
+        assert_eq!(values.next(), Some(None));
+        assert_eq!(values.next(), Some(Some(&1)));
+        assert_eq!(values.next(), Some(None));
+        assert_eq!(values.next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_each_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("a", 1).unwrap();
+        map.insert("c", 3).unwrap();
+        let keys = ["a", "b", "c"];
+        let mut values = map.get_each(&keys);
+        assert_eq!(values.next(), Some(Some(&1)));
+        assert_eq!(values.next(), Some(None));
+        assert_eq!(values.next(), Some(Some(&3)));
+        assert_eq!(values.next(), None);
+    }
+
+    #[test]
+    fn test_iter_indexed() {
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.insert("c", 3).unwrap();
+        map.insert("a", 1).unwrap();
+        map.insert("b", 2).unwrap();
+        for (idx, key, value) in map.iter_indexed() {
+            assert_eq!(unwrap(&map)[idx], Some((*key, *value)));
+        }
+        let mut iter = map.iter_indexed();
+        assert_eq!(iter.next(), Some((0, &"a", &1)));
+        assert_eq!(iter.next(), Some((1, &"b", &2)));
+        assert_eq!(iter.next(), Some((2, &"c", &3)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_iter_indexed_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("b", 2).unwrap();
+        map.insert("a", 1).unwrap();
+        let mut iter = map.iter_indexed();
+        assert_eq!(iter.next(), Some((0, &"a", &1)));
+        assert_eq!(iter.next(), Some((1, &"b", &2)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_get_index() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.get_index(0), Some((&"a", &1)));
+        assert_eq!(map.get_index(1), Some((&"b", &2)));
+        assert_eq!(map.get_index(2), None);
+        assert_eq!(map.get_index(4), None);
+        if let Some((_, value)) = map.get_index_mut(1) {
+            *value = 20
+        }
+        assert_eq!(map.get("b"), Some(&20));
+        assert_eq!(map.get_index_mut(2), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_index_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("b", 2).unwrap();
+        map.insert("a", 1).unwrap();
+        assert_eq!(map.get_index(0), Some((&"a", &1)));
+        assert_eq!(map.get_index(1), Some((&"b", &2)));
+        assert_eq!(map.get_index(2), None);
+        if let Some((_, value)) = map.get_index_mut(0) {
+            *value = 10
+        }
+        assert_eq!(map.get("a"), Some(&10));
+        assert_eq!(map.get_index_mut(2), None);
+    }
+
+    #[test]
+    fn test_swap_values() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert!(map.swap_values("d", "a"));
+        assert!(map.swap_values("b", "c"));
+        assert!(map.swap_values("b", "b"));
+        assert!(!map.swap_values("a", "e"));
+        assert!(!map.swap_values("e", "a"));
+        assert!(!map.swap_values("e", "e"));
+        assert_eq!(unwrap(&map), [Some(("a", 4)), Some(("b", 3)), Some(("c", 2)), Some(("d", 1))]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_swap_values_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("a", 1).unwrap();
+        map.insert("b", 2).unwrap();
+        assert!(map.swap_values("a", "b"));
+        assert!(map.swap_values("a", "a"));
+        assert!(!map.swap_values("a", "c"));
+        assert!(!map.swap_values("c", "c"));
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&1));
+        assert_eq!(map.len(), 2);
+        map.insert("d", 4).unwrap();
+        assert!(map.swap_values("d", "a"));
+        assert!(!map.swap_values("c", "a"));
+        assert!(!map.swap_values("b", "c"));
+        assert!(!map.swap_values("0", "b"));
+        assert!(map.iter().eq([("a", 4), ("b", 1), ("d", 2)].iter().map(|(key, value)| (key, value))));
+    }
+
+    #[test]
+    fn test_ceiling_floor() {
+        let mut pairs = [Some(("b", 1)), Some(("d", 2)), None, None];
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.ceiling("b"), Some((&"b", &1)));
+        assert_eq!(map.floor("d"), Some((&"d", &2)));
+        assert_eq!(map.ceiling("c"), Some((&"d", &2)));
+        assert_eq!(map.floor("c"), Some((&"b", &1)));
+        assert_eq!(map.ceiling("a"), Some((&"b", &1)));
+        assert_eq!(map.floor("a"), None);
+        assert_eq!(map.ceiling("e"), None);
+        assert_eq!(map.floor("e"), Some((&"d", &2)));
+
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.ceiling("a"), None);
+        assert_eq!(map.floor("a"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_ceiling_floor_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("b", 1).unwrap();
+        map.insert("d", 2).unwrap();
+        assert_eq!(map.ceiling("b"), Some((&"b", &1)));
+        assert_eq!(map.floor("d"), Some((&"d", &2)));
+        assert_eq!(map.ceiling("c"), Some((&"d", &2)));
+        assert_eq!(map.floor("c"), Some((&"b", &1)));
+        assert_eq!(map.ceiling("a"), Some((&"b", &1)));
+        assert_eq!(map.floor("a"), None);
+        assert_eq!(map.ceiling("e"), None);
+        assert_eq!(map.floor("e"), Some((&"d", &2)));
+    }
+
+    #[test]
+    fn test_is_borrowed() {
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert!(map.is_borrowed());
+        assert!(!map.is_owned());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_is_owned() {
+        let map: ManagedMap<&str, u32> = ManagedMap::Owned(BTreeMap::new());
+        assert!(map.is_owned());
+        assert!(!map.is_borrowed());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_into_btree_map() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("a", 1).unwrap();
+        let map = map.into_btree_map().unwrap();
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.len(), 1);
+
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.into_btree_map(), None);
+    }
+
+    #[test]
+    fn test_from_array() {
+        fn with_capacity<const N: usize>(pairs: &mut [Option<(&'static str, u32)>; N]) -> usize {
+            let map = ManagedMap::from(pairs);
+            assert!(map.is_valid());
+            N - map.len()
+        }
+
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), None, None];
+        assert_eq!(with_capacity(&mut pairs), 2);
+
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs);
+        assert_eq!(map.insert("a", 1), Ok(None));
+        assert_eq!(unwrap(&map), [Some(("a", 1)), None, None, None]);
+    }
+
+    #[test]
+    fn test_try_from_sorted() {
+        static mut TABLE: [Option<(u8, u8)>; 4] = [Some((1, 10)), Some((2, 20)), None, None];
+
+        let table = unsafe { &mut *core::ptr::addr_of_mut!(TABLE) };
+        let mut map: ManagedMap<'static, u8, u8> = ManagedMap::try_from_sorted(table).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.insert(0, 0), Ok(None));
+        assert_eq!(map.get(&1), Some(&10));
+        assert!(map.is_valid());
+
+        let mut pairs = all_pairs_empty();
+        assert!(ManagedMap::try_from_sorted(&mut pairs[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_try_from_sorted_corrupt() {
+        let mut pairs = [Some(("b", 2)), Some(("a", 1)), None, None];
+        assert_eq!(ManagedMap::try_from_sorted(&mut pairs[..]).err(), Some(Error::Corrupt));
+        let mut pairs = [Some(("a", 1)), None, Some(("b", 2)), None];
+        assert_eq!(ManagedMap::try_from_sorted(&mut pairs[..]).err(), Some(Error::Corrupt));
+        let mut pairs = [Some(("a", 1)), Some(("a", 2)), None, None];
+        assert_eq!(ManagedMap::try_from_sorted(&mut pairs[..]).err(), Some(Error::Corrupt));
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn test_packed_round_trip() {
+        let mut pairs = [Some((1u16, 10u32)), Some((3, 30)), None, None];
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut bytes = [0xff; 32];
+        assert_eq!(map.serialize_packed(&mut bytes), Ok(16));
+        assert_eq!(bytes[..4], [2, 0, 0, 0]);
+        assert_eq!(bytes[4..6], 1u16.to_ne_bytes());
+        assert_eq!(bytes[6..10], 10u32.to_ne_bytes());
+
+        let mut scratch = [Some((7, 70)); 3];
+        let map = ManagedMap::<u16, u32>::deserialize_packed(&mut scratch, &bytes).unwrap();
+        assert!(map.is_valid());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&1), Some(&10));
+        assert_eq!(map.get(&3), Some(&30));
+        assert_eq!(map.get(&7), None);
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn test_packed_empty() {
+        let mut pairs: [Option<(u8, u8)>; 2] = [None; 2];
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut bytes = [0; 4];
+        assert_eq!(map.serialize_packed(&mut bytes), Ok(4));
+        let mut scratch: [Option<(u8, u8)>; 0] = [];
+        assert!(ManagedMap::deserialize_packed(&mut scratch, &bytes).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn test_packed_errors() {
+        let mut pairs = [Some((1u8, 10u8)), Some((2, 20))];
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut bytes = [0; 8];
+        assert_eq!(map.serialize_packed(&mut bytes[..7]), Err(PackedError::BufferTooSmall));
+        assert_eq!(bytes, [0; 8]);
+        assert_eq!(map.serialize_packed(&mut bytes), Ok(8));
+
+        let mut scratch = [None; 2];
+        assert_eq!(ManagedMap::<u8, u8>::deserialize_packed(&mut scratch, &bytes[..7]).err(),
+                   Some(PackedError::Truncated));
+        assert_eq!(ManagedMap::<u8, u8>::deserialize_packed(&mut scratch, &bytes[..3]).err(),
+                   Some(PackedError::Truncated));
+        let mut small = [None; 1];
+        assert_eq!(ManagedMap::<u8, u8>::deserialize_packed(&mut small, &bytes).err(),
+                   Some(PackedError::Full));
+        let unsorted = [2, 0, 0, 0, 2, 20, 1, 10];
+        assert_eq!(ManagedMap::<u8, u8>::deserialize_packed(&mut scratch, &unsorted).err(),
+                   Some(PackedError::Unsorted));
+    }
+
+    #[test]
+    fn test_from_unsorted_in_place() {
+        let mut pairs = [Some(("c", 3)), Some(("a", 1)), Some(("d", 4)), Some(("b", 2))];
+        let map = ManagedMap::from_unsorted_in_place(&mut pairs[..]).unwrap();
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), all_pairs_full());
+
+        let mut pairs = [None, Some(("c", 3)), None, Some(("a", 1))];
+        let map = ManagedMap::from_unsorted_in_place(&mut pairs[..]).unwrap();
+        assert!(map.is_valid());
+        assert_eq!(map.len(), 2);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("c", 3)), None, None]);
+
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from_unsorted_in_place(&mut pairs[..]).unwrap();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_from_unsorted_in_place_duplicate() {
+        let mut pairs = [Some(("b", 2)), None, Some(("a", 1)), Some(("b", 3))];
+        assert_eq!(ManagedMap::from_unsorted_in_place(&mut pairs[..]).err(),
+                   Some(Error::DuplicateKey));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), None, None];
+        assert!(ManagedMap::from(&mut pairs[..]).is_valid());
+        let mut pairs = all_pairs_empty();
+        assert!(ManagedMap::from(&mut pairs[..]).is_valid());
+        let mut pairs = all_pairs_full();
+        assert!(ManagedMap::from(&mut pairs[..]).is_valid());
+
+        let mut pairs = [Some(("b", 2)), Some(("a", 1)), None, None];
+        assert!(!ManagedMap::Borrowed(&mut pairs[..], 2).is_valid());
+        let mut pairs = [Some(("a", 1)), Some(("a", 2)), None, None];
+        assert!(!ManagedMap::Borrowed(&mut pairs[..], 2).is_valid());
+        let mut pairs = [Some(("a", 1)), None, Some(("b", 2)), None];
+        assert!(!ManagedMap::Borrowed(&mut pairs[..], 1).is_valid());
+        assert!(!ManagedMap::Borrowed(&mut pairs[..], 2).is_valid());
+        let mut pairs = [Some(("a", 1)), None];
+        assert!(!ManagedMap::Borrowed(&mut pairs[..], 3).is_valid());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_is_valid_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("a", 1).unwrap();
+        assert!(map.is_valid());
+    }
+
+    #[test]
+    fn test_insert_empty() {
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.len(), 0);
+        assert!(map.is_empty());
+
+        assert_eq!(map.insert("a", 1), Ok(None));
+        assert_eq!(map.len(), 1);
+        assert!(!map.is_empty());
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), None, None, None]);
+    }
+
+    #[test]
+    fn test_insert_replace() {
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert("a", 1), Ok(None));
+        assert_eq!(map.insert("a", 2), Ok(Some(1)));
+        assert_eq!(map.len(), 1);
+        assert!(!map.is_empty());
+        assert_eq!(unwrap(&map),       [Some(("a", 2)), None, None, None]);
+    }
+
+    #[test]
+    fn test_insert_full() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert("q", 1), Err(CapacityError::new(("q", 1))));
+        assert_eq!(map.len(), 4);
+        assert_eq!(unwrap(&map),       all_pairs_full());
+    }
+
+    #[test]
+    fn test_insert_one() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert("b", 2), Ok(None));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), None, None]);
+    }
+
+    #[test]
+    fn test_insert_shift() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert("c", 3), Ok(None));
+        assert_eq!(map.insert("b", 2), Ok(None));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), None]);
+    }
+
+    #[test]
+    fn test_insert_shift_minimal() {
+        let mut pairs = [None; 6];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert_indexed("b", 2), Ok((0, None)));
+        assert_eq!(map.insert_indexed("d", 4), Ok((1, None)));
+        assert_eq!(map.insert_indexed("a", 1), Ok((0, None)));
+        assert_eq!(map.insert_indexed("c", 3), Ok((2, None)));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)),
+                                        Some(("d", 4)), None, None]);
+        assert_eq!(map.insert_indexed("f", 6), Ok((4, None)));
+        assert_eq!(map.insert_indexed("e", 5), Ok((4, None)));
+        assert_eq!(map.insert_indexed("g", 7), Err(CapacityError::new(("g", 7))));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)),
+                                        Some(("d", 4)), Some(("e", 5)), Some(("f", 6))]);
+    }
+
+    #[test]
+    fn test_insert_no_space() {
+        // Zero-sized backing store
+        let mut map = ManagedMap::from(&mut [][..]);
+        assert_eq!(map.insert("a", 1), Err(CapacityError::new(("a", 1))));
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut map: ManagedMap<&str, u32> = ManagedMap::from(&mut [][..]);
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.get_mut("a"), None);
+        assert_eq!(map.insert("a", 1), Err(CapacityError::new(("a", 1))));
+        assert_eq!(map.insert_indexed("a", 1), Err(CapacityError::new(("a", 1))));
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.range::<&str, _>(..).next(), None);
+        assert_eq!(map.iter().next(), None);
+        assert_eq!(map.len(), 0);
+        assert!(map.is_empty());
+        map.clear();
+    }
+
+    #[test]
+    fn test_remove_nonexistent() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_one() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.remove("a"), Some(1));
+        assert_eq!(map.len(), 3);
+        assert_eq!(unwrap(&map),    [Some(("b", 2)), Some(("c", 3)), Some(("d", 4)), None]);
+    }
+
+    #[test]
+    fn test_iter_none() {
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut iter = map.iter();
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_iter_one() {
+        let mut pairs = one_pair_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut iter = map.iter();
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        assert_eq!(iter.next(), Some((&"a", &1)));
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_iter_full() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut iter = map.iter();
+        assert_eq!(iter.size_hint(), (4, Some(4)));
+        assert_eq!(iter.next(), Some((&"a", &1)));
+        assert_eq!(iter.next(), Some((&"b", &2)));
+        assert_eq!(iter.next(), Some((&"c", &3)));
+        assert_eq!(iter.next(), Some((&"d", &4)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut_full() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+
+        {
+            let mut iter = map.iter_mut();
+            assert_eq!(iter.size_hint(), (0, Some(4)));
+            for (_k, v) in &mut iter {
+                *v += 1;
+            }
+            assert_eq!(iter.size_hint(), (0, Some(0)));
+            // Scope for `iter` ends here so that it can be borrowed
+            // again with the following `iter`.
+        }
+        {
+            let mut iter = map.iter();
+            assert_eq!(iter.next(), Some((&"a", &2)));
+            assert_eq!(iter.next(), Some((&"b", &3)));
+            assert_eq!(iter.next(), Some((&"c", &4)));
+            assert_eq!(iter.next(), Some((&"d", &5)));
+            assert_eq!(iter.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_into() {
+        let mut pairs = [Some(("a", 1)), Some(("c", 3)), None];
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut scratch = all_pairs_full();
+        {
+            let mut snapshot = map.snapshot_into(&mut scratch).unwrap();
+            assert!(snapshot.is_valid());
+            assert_eq!(snapshot.insert("b", 2), Ok(None));
+            assert_eq!(map.get("b"), None);
+        }
+        assert_eq!(scratch, [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), None]);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("c", 3)), None]);
+
+        let mut scratch = [None];
+        assert_eq!(map.snapshot_into(&mut scratch).err(), Some(Error::Full));
+        assert_eq!(scratch, [None]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_snapshot_into_owned() {
+        let map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        let mut scratch = [Some(("z", 26)), None, None, None, Some(("y", 25))];
+        {
+            let snapshot = map.snapshot_into(&mut scratch).unwrap();
+            assert!(snapshot.is_borrowed());
+            assert_eq!(snapshot.len(), 4);
+            assert!(snapshot.iter().eq(map.iter()));
+        }
+        assert_eq!(scratch, [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), Some(("d", 4)), None]);
+
+        let mut scratch = all_pairs_empty();
+        scratch[3] = Some(("z", 26));
+        let mut short = [None, None, None];
+        assert_eq!(map.snapshot_into(&mut short).err(), Some(Error::Full));
+        assert_eq!(short, [None, None, None]);
+        assert_eq!(map.snapshot_into(&mut scratch).map(|snapshot| snapshot.len()), Ok(4));
+    }
+
+    #[test]
+    fn test_as_borrowed_into_borrowed() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        let mut scratch = all_pairs_empty();
+        {
+            let mut view = map.as_borrowed_into(&mut scratch).unwrap();
+            assert_eq!(view.insert("b", 2), Ok(None));
+        }
+        assert_eq!(scratch, all_pairs_empty());
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), None, None]);
+    }
 
-        let mut range = map.range(.."b");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_as_borrowed_into_owned() {
+        let mut btree = BTreeMap::new();
+        btree.insert("b", 2);
+        btree.insert("a", 1);
+        let mut map = ManagedMap::Owned(btree);
+        let mut scratch = all_pairs_full();
+        {
+            let view = map.as_borrowed_into(&mut scratch).unwrap();
+            assert_eq!(view.len(), 2);
+            assert_eq!(view.get("a"), Some(&1));
+            assert_eq!(view.get("b"), Some(&2));
+        }
+        assert_eq!(scratch,            [Some(("a", 1)), Some(("b", 2)), None, None]);
+        assert_eq!(map.len(), 2);
+    }
 
-        let mut range = map.range(.."a");
-        assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_as_borrowed_into_owned_too_small() {
+        let mut btree = BTreeMap::new();
+        btree.insert("a", 1);
+        btree.insert("b", 2);
+        let mut map = ManagedMap::Owned(btree);
+        let mut scratch = [None; 1];
+        assert_eq!(map.as_borrowed_into(&mut scratch).err(), Some(Error::Full));
+        assert_eq!(scratch, [None]);
+    }
 
-        let mut range = map.range::<&str, _>(..);
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
-        assert_eq!(range.next_back(), None);
+    #[test]
+    fn test_custom_key_order() {
+        let mut pairs = [None; 4];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert(CaseInsensitive("b"), 2), Ok(None));
+        assert_eq!(map.insert(CaseInsensitive("A"), 1), Ok(None));
+        assert!(map.iter().map(|(key, _)| key.0).eq(["A", "b"].iter().cloned()));
+        assert_eq!(map.insert(CaseInsensitive("a"), 3), Ok(Some(1)));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&CaseInsensitive("B")), Some(&2));
+        assert_eq!(map.get(&CaseInsensitive("a")), Some(&3));
     }
 
     #[test]
-    fn test_range_full_exclude_left() {
+    #[cfg(feature = "std")]
+    fn test_custom_key_order_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.insert(CaseInsensitive("B"), 2), Ok(None));
+        assert_eq!(map.insert(CaseInsensitive("b"), 3), Ok(Some(2)));
+        assert_eq!(map.get(&CaseInsensitive("b")), Some(&3));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_insert_indexed() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert_indexed("c", 3), Ok((1, None)));
+        assert_eq!(map.insert_indexed("b", 2), Ok((1, None)));
+        assert_eq!(map.insert_indexed("0", 0), Ok((0, None)));
+        assert_eq!(map.insert_indexed("c", 4), Ok((3, Some(3))));
+        assert_eq!(map.insert_indexed("d", 5), Err(CapacityError::new(("d", 5))));
+        assert_eq!(unwrap(&map),       [Some(("0", 0)), Some(("a", 1)), Some(("b", 2)), Some(("c", 4))]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_insert_indexed_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.insert_indexed("c", 3), Ok((0, None)));
+        assert_eq!(map.insert_indexed("a", 1), Ok((0, None)));
+        assert_eq!(map.insert_indexed("b", 2), Ok((1, None)));
+        assert_eq!(map.insert_indexed("c", 4), Ok((2, Some(3))));
+    }
+
+    #[test]
+    fn test_reload_sorted() {
         let mut pairs = all_pairs_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 4);
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.reload_sorted([("a", 1), ("b", 2), ("b", 3), ("c", 4), ("c", 5)]),
+                   Ok(()));
+        assert_eq!(map.len(), 3);
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 3)), Some(("c", 5)), None]);
+    }
 
-        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("a")));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("b")));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("c")));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("d")));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Excluded("a"), Excluded("e")));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
+    #[test]
+    fn test_reload_sorted_unsorted() {
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.reload_sorted([("b", 2), ("c", 3), ("a", 1), ("c", 4)]), Ok(()));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 4)), None]);
     }
 
     #[test]
-    fn test_range_full_include_right() {
+    fn test_reload_sorted_overflow() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.reload_sorted([("b", 2), ("c", 3), ("d", 4), ("d", 5), ("e", 6),
+                                          ("f", 7)]),
+                   Err(CapacityError::new(("f", 7))));
+        assert_eq!(unwrap(&map),       [Some(("b", 2)), Some(("c", 3)), Some(("d", 5)), Some(("e", 6))]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_reload_sorted_owned() {
+        let mut btree = BTreeMap::new();
+        btree.insert("q", 0);
+        let mut map = ManagedMap::Owned(btree);
+        assert_eq!(map.reload_sorted([("a", 1), ("a", 2), ("b", 3)]), Ok(()));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.get("b"), Some(&3));
+        assert_eq!(map.get("q"), None);
+    }
+
+    #[test]
+    fn test_checked_valid() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.checked_get("a"), Ok(&1));
+        assert_eq!(map.checked_get("b"), Err(Error::NotFound));
+        assert_eq!(map.checked_insert("b", 2), Ok(None));
+        assert_eq!(map.checked_insert("b", 3), Ok(Some(2)));
+        assert_eq!(map.checked_remove("a"), Ok(1));
+        assert_eq!(map.checked_remove("a"), Err(Error::NotFound));
+        assert_eq!(unwrap(&map),       [Some(("b", 3)), None, None, None]);
+    }
+
+    #[test]
+    fn test_checked_full() {
         let mut pairs = all_pairs_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 4);
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        let err = map.checked_insert("e", 5).unwrap_err();
+        assert_eq!(err.kind(), Error::Full);
+        assert_eq!(err.into_inner(), ("e", 5));
+        let mut map = ManagedMap::from(&mut [][..]);
+        assert_eq!(map.checked_insert("e", 5), Err(InsertError::new(Error::Full, ("e", 5))));
+    }
 
-        let mut range = map.range::<&str, _>((Included("b"), Included("a")));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("b")));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("c")));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("d")));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("e")));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
+    #[test]
+    fn test_checked_corrupt() {
+        let gappy = [Some(("a", 1)), None, Some(("c", 3)), None];
+        let unsorted = [Some(("b", 2)), Some(("a", 1)), None, None];
+        let duplicate = [Some(("a", 1)), Some(("a", 1)), None, None];
+        for corrupt in [gappy, unsorted, duplicate].iter() {
+            let mut pairs = *corrupt;
+            let mut map = ManagedMap::from(&mut pairs[..]);
+            assert_eq!(map.checked_get("a"), Err(Error::Corrupt));
+            assert_eq!(map.checked_get("c"), Err(Error::Corrupt));
+            assert_eq!(map.checked_insert("b", 2), Err(InsertError::new(Error::Corrupt, ("b", 2))));
+            assert_eq!(map.checked_remove("a"), Err(Error::Corrupt));
+            assert_eq!(unwrap(&map), *corrupt);
+        }
+    }
 
-        let mut range = map.range::<&str, _>((Included("b"), Included("a")));
-        assert_eq!(range.next_back(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("b")));
-        assert_eq!(range.next_back(), Some((&"b", &2)));
-        assert_eq!(range.next_back(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("c")));
-        assert_eq!(range.next_back(), Some((&"c", &3)));
-        assert_eq!(range.next_back(), Some((&"b", &2)));
-        assert_eq!(range.next_back(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("d")));
-        assert_eq!(range.next_back(), Some((&"d", &4)));
-        assert_eq!(range.next_back(), Some((&"c", &3)));
-        assert_eq!(range.next_back(), Some((&"b", &2)));
-        assert_eq!(range.next_back(), None);
-        let mut range = map.range::<&str, _>((Included("b"), Included("e")));
-        assert_eq!(range.next_back(), Some((&"d", &4)));
-        assert_eq!(range.next_back(), Some((&"c", &3)));
-        assert_eq!(range.next_back(), Some((&"b", &2)));
-        assert_eq!(range.next_back(), None);
+    #[test]
+    fn test_checked_corrupt_len() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::Borrowed(&mut pairs, 2);
+        assert_eq!(map.checked_get("a"), Err(Error::Corrupt));
+        assert_eq!(map.checked_insert("b", 2), Err(InsertError::new(Error::Corrupt, ("b", 2))));
+        assert_eq!(map.checked_remove("a"), Err(Error::Corrupt));
     }
 
     #[test]
-    fn test_range_full() {
+    #[cfg(feature = "std")]
+    fn test_checked_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.checked_insert("a", 1), Ok(None));
+        assert_eq!(map.checked_get("a"), Ok(&1));
+        assert_eq!(map.checked_get("b"), Err(Error::NotFound));
+        assert_eq!(map.checked_remove("a"), Ok(1));
+        assert_eq!(map.checked_remove("a"), Err(Error::NotFound));
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut pairs = all_pairs_empty();
+        {
+            let mut map = ManagedMap::from(&mut pairs[..]);
+            assert_eq!(map.insert("c", 3), Ok(None));
+            assert_eq!(map.insert("a", 1), Ok(None));
+            let mut iter = map.into_iter();
+            assert_eq!(iter.next(), Some(("a", 1)));
+            assert_eq!(iter.next(), Some(("c", 3)));
+            assert_eq!(iter.next(), None);
+        }
+        assert_eq!(pairs, all_pairs_empty());
+    }
+
+    #[test]
+    fn test_into_iter_drop_early() {
         let mut pairs = all_pairs_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 4);
+        {
+            let map = ManagedMap::from(&mut pairs[..]);
+            let mut iter = map.into_iter();
+            assert_eq!(iter.next(), Some(("a", 1)));
+            assert_eq!(iter.next(), Some(("b", 2)));
+        }
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert!(map.is_valid());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("c"), Some(&3));
+        assert_eq!(map.get("d"), Some(&4));
+        assert_eq!(map.insert("a", 5), Ok(None));
+        assert_eq!(unwrap(&map), [Some(("a", 5)), Some(("c", 3)), Some(("d", 4)), None]);
+    }
 
-        let mut range = map.range("0".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("0".."b");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("0".."c");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("0".."d");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("0".."e");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_into_iter_collect() {
+        use std::vec::Vec;
 
-        let mut range = map.range("a".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("a".."b");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("a".."c");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("a".."d");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("a".."e");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        let borrowed: Vec<_> = map.into_iter().collect();
+        assert_eq!(borrowed, [("a", 1), ("b", 2), ("c", 3), ("d", 4)]);
 
-        let mut range = map.range("b".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."b");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."c");
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."d");
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."e");
-        assert_eq!(range.next(), Some((&"b", &2)));
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
+        let mut btree = BTreeMap::new();
+        btree.insert("b", 2);
+        btree.insert("a", 1);
+        let map = ManagedMap::Owned(btree);
+        let owned: Vec<_> = map.into_iter().collect();
+        assert_eq!(owned, [("a", 1), ("b", 2)]);
+    }
 
-        let mut range = map.range("c".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("c".."b");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("c".."c");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("c".."d");
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("c".."e");
-        assert_eq!(range.next(), Some((&"c", &3)));
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
+    #[test]
+    fn test_get_or_insert_present() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.get_or_insert("a", 5), Some(&mut 1));
+        assert_eq!(map.len(), 1);
+    }
 
-        let mut range = map.range("d".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("d".."b");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("d".."c");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("d".."d");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("d".."e");
-        assert_eq!(range.next(), Some((&"d", &4)));
-        assert_eq!(range.next(), None);
+    #[test]
+    fn test_get_or_insert_absent() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        *map.get_or_insert("0", 5).unwrap() += 1;
+        assert_eq!(unwrap(&map),       [Some(("0", 6)), Some(("a", 1)), None, None]);
+    }
 
-        let mut range = map.range("e".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("e".."b");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("e".."c");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("e".."d");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("e".."e");
-        assert_eq!(range.next(), None);
+    #[test]
+    fn test_get_or_insert_full() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.get_or_insert("e", 5), None);
+        assert_eq!(map.get_or_insert("d", 5), Some(&mut 4));
+        assert_eq!(map.get_or_insert_with("e", || unreachable!()), None);
+        let mut map = ManagedMap::from(&mut [][..]);
+        assert_eq!(map.get_or_insert("e", 5), None);
+    }
+
+    #[test]
+    fn test_try_get_or_insert_with() {
+        let mut pairs = [Some(("b", 2)), None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.try_get_or_insert_with("b", || unreachable!()), Ok(&mut 2));
+        *map.try_get_or_insert_with("a", || 1).unwrap() += 10;
+        assert_eq!(map.try_get_or_insert_with("c", || 3), Ok(&mut 3));
+        assert_eq!(unwrap(&map),       [Some(("a", 11)), Some(("b", 2)), Some(("c", 3))]);
+    }
+
+    #[test]
+    fn test_try_get_or_insert_with_full() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.try_get_or_insert_with("d", || unreachable!()), Ok(&mut 4));
+        let err = map.try_get_or_insert_with("e", || unreachable!()).unwrap_err();
+        assert_eq!(err, CapacityError::new("e"));
+        assert_eq!(err.into_inner(), "e");
+        let mut map = ManagedMap::from(&mut [][..]);
+        assert_eq!(map.try_get_or_insert_with("e", || 5), Err(CapacityError::new("e")));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_try_get_or_insert_with_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.try_get_or_insert_with("a", || 1), Ok(&mut 1));
+        assert_eq!(map.try_get_or_insert_with("a", || unreachable!()), Ok(&mut 1));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_or_insert_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.get_or_insert("a", 1), Some(&mut 1));
+        assert_eq!(map.get_or_insert("a", 2), Some(&mut 1));
+        assert_eq!(map.get_or_insert_with("b", || 3), Some(&mut 3));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_eq_btree_map() {
+        let mut btree = BTreeMap::new();
+        btree.insert("a", 1);
+
+        let mut pairs = one_pair_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert!(map == btree);
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert!(map != btree);
+        let map = ManagedMap::from(&mut [][..]);
+        assert!(map != btree);
+        assert!(map == BTreeMap::new());
+
+        let mut map = ManagedMap::Owned(btree.clone());
+        assert!(map == btree);
+        assert_eq!(map.insert("a", 2), Ok(Some(1)));
+        assert!(map != btree);
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert_many([("c", 3), ("a", 10), ("b", 2)]), 3);
+        assert_eq!(unwrap(&map),       [Some(("a", 10)), Some(("b", 2)), Some(("c", 3)), None]);
     }
 
     #[test]
-    fn test_range_one_pair() {
+    fn test_insert_many_overflow() {
         let mut pairs = one_pair_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 1);
-
-        let mut range = map.range("0".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("0".."b");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("0".."c");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), None);
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert_many([("d", 4), ("c", 3), ("b", 2), ("e", 5), ("a", 10)]), 3);
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), Some(("d", 4))]);
+    }
 
-        let mut range = map.range("a".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("a".."b");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), None);
-        let mut range = map.range("a".."c");
-        assert_eq!(range.next(), Some((&"a", &1)));
-        assert_eq!(range.next(), None);
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_insert_many_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.insert_many([("a", 1), ("b", 2), ("c", 3)]), 3);
+        assert_eq!(map.len(), 3);
+    }
 
-        let mut range = map.range("b".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."b");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."c");
-        assert_eq!(range.next(), None);
+    #[test]
+    fn test_extend_ref() {
+        static TABLE: [(&str, u32); 3] = [("c", 3), ("a", 1), ("b", 2)];
+        let mut pairs = [None; 4];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.extend(TABLE.iter());
+        map.extend(&[("a", 10)]);
+        assert_eq!(unwrap(&map),       [Some(("a", 10)), Some(("b", 2)), Some(("c", 3)), None]);
     }
 
     #[test]
-    fn test_range_empty() {
-        let mut pairs = all_pairs_empty();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 0);
+    fn test_extend_ref_full() {
+        let mut pairs = one_pair_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.extend(&[("b", 2), ("c", 3), ("d", 4), ("e", 5), ("a", 10)]);
+        assert_eq!(unwrap(&map),       [Some(("a", 10)), Some(("b", 2)), Some(("c", 3)), Some(("d", 4))]);
+    }
 
-        let mut range = map.range("b".."a");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."b");
-        assert_eq!(range.next(), None);
-        let mut range = map.range("b".."c");
-        assert_eq!(range.next(), None);
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_extend_ref_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.extend(&[("b", 2), ("a", 1)]);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&1));
     }
 
     #[test]
-    fn test_get_mut_some() {
+    fn test_insert_with_merge() {
         let mut pairs = all_pairs_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert_with("b", 10, |old, new| old + new), Ok(()));
+        assert_eq!(map.get("b"), Some(&12));
+        assert_eq!(map.insert_with("e", 5, |_, _| unreachable!()), Err(CapacityError::new(("e", 5))));
         assert_eq!(map.len(), 4);
-        assert!(!map.is_empty());
-        assert_eq!(map.get_mut("a"), Some(&mut 1));
-        assert_eq!(map.get_mut("b"), Some(&mut 2));
-        assert_eq!(map.get_mut("c"), Some(&mut 3));
-        assert_eq!(map.get_mut("d"), Some(&mut 4));
     }
 
     #[test]
-    fn test_get_mut_none() {
+    #[cfg(feature = "std")]
+    fn test_insert_with_merge_panics() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.insert_with("b", 10, |_, _| panic!("merge failed"))
+        }));
+        assert!(result.is_err());
+        assert!(map.is_valid());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.insert("e", 5), Ok(None));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("c", 3)), Some(("d", 4)), Some(("e", 5))]);
+    }
+
+    #[test]
+    fn test_insert_with_fresh() {
         let mut pairs = one_pair_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.get_mut("q"), None);
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.insert_with("b", 2, |_, _| unreachable!()), Ok(()));
+        assert_eq!(map.insert_with("b", 3, |old, new| old * new), Ok(()));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 6)), None, None]);
     }
 
     #[test]
-    fn test_insert_empty() {
-        let mut pairs = all_pairs_empty();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.len(), 0);
-        assert!(map.is_empty());
+    #[cfg(feature = "std")]
+    fn test_insert_with_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        assert_eq!(map.insert_with("a", 1, |_, _| unreachable!()), Ok(()));
+        assert_eq!(map.insert_with("a", 2, |old, new| old + new), Ok(()));
+        assert_eq!(map.get("a"), Some(&3));
+    }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_new() {
+        let mut map = ManagedMap::new();
+        assert!(map.is_empty());
         assert_eq!(map.insert("a", 1), Ok(None));
-        assert_eq!(map.len(), 1);
-        assert!(!map.is_empty());
-        assert_eq!(unwrap(&map),       [Some(("a", 1)), None, None, None]);
+        assert_eq!(map.get("a"), Some(&1));
+        let map: ManagedMap<&str, u32> = ManagedMap::default();
+        assert!(map.is_empty());
     }
 
     #[test]
-    fn test_insert_replace() {
-        let mut pairs = all_pairs_empty();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.insert("a", 1), Ok(None));
-        assert_eq!(map.insert("a", 2), Ok(Some(1)));
-        assert_eq!(map.len(), 1);
-        assert!(!map.is_empty());
-        assert_eq!(unwrap(&map),       [Some(("a", 2)), None, None, None]);
+    #[cfg(feature = "std")]
+    fn test_iter_by_value() {
+        let mut pairs = [Some(("a", 3)), Some(("b", 1)), Some(("c", 2)), Some(("d", 1))];
+        let map = ManagedMap::from(&mut pairs[..]);
+        let mut iter = map.iter_by_value();
+        assert_eq!(iter.next(), Some((&"b", &1)));
+        assert_eq!(iter.next(), Some((&"d", &1)));
+        assert_eq!(iter.next(), Some((&"c", &2)));
+        assert_eq!(iter.next(), Some((&"a", &3)));
+        assert_eq!(iter.next(), None);
+
+        let mut map = ManagedMap::new();
+        assert_eq!(map.insert("x", 20), Ok(None));
+        assert_eq!(map.insert("y", 10), Ok(None));
+        assert!(map.iter_by_value().eq([(&"y", &10), (&"x", &20)].iter().cloned()));
     }
 
     #[test]
-    fn test_insert_full() {
-        let mut pairs = all_pairs_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.insert("q", 1), Err(("q", 1)));
-        assert_eq!(map.len(), 4);
-        assert_eq!(unwrap(&map),       all_pairs_full());
+    fn test_merge_sorted_disjoint() {
+        let mut pairs = [Some(("b", 2)), Some(("d", 4)), None, None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.merge_sorted([("a", 1), ("c", 3), ("e", 5)].iter().cloned()), Ok(()));
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)),
+                                  Some(("d", 4)), Some(("e", 5))]);
     }
 
     #[test]
-    fn test_insert_one() {
-        let mut pairs = one_pair_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.insert("b", 2), Ok(None));
-        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), None, None]);
+    fn test_merge_sorted_overlapping() {
+        let mut pairs = [Some(("a", 1)), Some(("c", 3)), None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        let batch = [("a", 10), ("b", 2), ("b", 20), ("c", 30)];
+        assert_eq!(map.merge_sorted(batch.iter().cloned()), Ok(()));
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 10)), Some(("b", 20)), Some(("c", 30)), None]);
+
+        assert_eq!(map.merge_sorted([].iter().cloned()), Ok(()));
+        assert_eq!(unwrap(&map), [Some(("a", 10)), Some(("b", 20)), Some(("c", 30)), None]);
+
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.merge_sorted([("a", 1), ("b", 2)].iter().cloned()), Ok(()));
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 2)), None, None]);
     }
 
     #[test]
-    fn test_insert_shift() {
-        let mut pairs = one_pair_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.insert("c", 3), Ok(None));
-        assert_eq!(map.insert("b", 2), Ok(None));
-        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), None]);
+    fn test_merge_sorted_overflow() {
+        let mut pairs = [Some(("b", 2)), Some(("d", 4)), None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        let batch = [("a", 1), ("b", 20), ("c", 3), ("d", 40)];
+        assert_eq!(map.merge_sorted(batch.iter().cloned()), Err(CapacityError::new(("c", 3))));
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 20)), Some(("d", 4))]);
+
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.merge_sorted([("d", 40), ("e", 5)].iter().cloned()),
+                   Err(CapacityError::new(("e", 5))));
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), Some(("d", 40))]);
     }
 
     #[test]
-    fn test_insert_no_space() {
-        // Zero-sized backing store
-        let mut map = ManagedMap::Borrowed(&mut []);
-        assert_eq!(map.insert("a", 1), Err(("a", 1)));
+    fn test_merge_sorted_unsorted() {
+        let mut pairs = [Some(("b", 2)), Some(("d", 4)), None, None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.merge_sorted([("c", 3), ("a", 1), ("d", 40)].iter().cloned()), Ok(()));
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)),
+                                  Some(("d", 40)), None]);
+
+        let mut pairs = [Some(("b", 2)), None, None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.merge_sorted([("c", 3), ("a", 1), ("d", 4)].iter().cloned()),
+                   Err(CapacityError::new(("d", 4))));
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 2)), Some(("c", 3))]);
     }
 
     #[test]
-    fn test_remove_nonexistent() {
-        let mut pairs = one_pair_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.remove("b"), None);
-        assert_eq!(map.len(), 1);
+    #[cfg(feature = "std")]
+    fn test_merge_sorted_owned() {
+        let mut map = ManagedMap::Owned(BTreeMap::new());
+        map.insert("a", 1).unwrap();
+        assert_eq!(map.merge_sorted([("a", 10), ("b", 2)].iter().cloned()), Ok(()));
+        assert_eq!(map.get("a"), Some(&10));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.len(), 2);
     }
 
     #[test]
-    fn test_remove_one() {
+    fn test_truncate() {
         let mut pairs = all_pairs_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
-        assert_eq!(map.remove("a"), Some(1));
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.truncate(4);
+        assert_eq!(unwrap(&map), all_pairs_full());
+        map.truncate(10);
+        assert_eq!(unwrap(&map), all_pairs_full());
+        map.truncate(2);
+        assert_eq!(map.len(), 2);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("b", 2)), None, None]);
+        map.truncate(0);
+        assert!(map.is_empty());
+        assert_eq!(unwrap(&map), all_pairs_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_truncate_owned() {
+        let mut map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        map.truncate(10);
+        assert_eq!(map.len(), 4);
+        map.truncate(3);
         assert_eq!(map.len(), 3);
-        assert_eq!(unwrap(&map),    [Some(("b", 2)), Some(("c", 3)), Some(("d", 4)), None]);
+        map.truncate(2);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.get("c"), None);
+        map.truncate(2);
+        assert_eq!(map.len(), 2);
+        map.truncate(0);
+        assert!(map.is_empty());
     }
 
     #[test]
-    fn test_iter_none() {
+    fn test_iter_split_at() {
+        let mut pairs = all_pairs_full();
+        let map = ManagedMap::from(&mut pairs[..]);
+        let (mut below, mut above) = map.iter_split_at("c");
+        assert_eq!(below.next(), Some((&"a", &1)));
+        assert_eq!(below.next(), Some((&"b", &2)));
+        assert_eq!(below.next(), None);
+        assert_eq!(above.next(), Some((&"c", &3)));
+        assert_eq!(above.next_back(), Some((&"d", &4)));
+        assert_eq!(above.next(), None);
+
+        let (below, above) = map.iter_split_at("bb");
+        assert_eq!(below.count(), 2);
+        assert_eq!(above.count(), 2);
+        let (below, above) = map.iter_split_at("0");
+        assert_eq!(below.count(), 0);
+        assert_eq!(above.count(), 4);
+        let (below, above) = map.iter_split_at("z");
+        assert_eq!(below.count(), 4);
+        assert_eq!(above.count(), 0);
+
         let mut pairs = all_pairs_empty();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        let mut iter = map.iter();
-        assert_eq!(iter.size_hint(), (0, Some(0)));
-        assert_eq!(iter.next(), None);
+        let map = ManagedMap::from(&mut pairs[..]);
+        let (below, above) = map.iter_split_at("a");
+        assert_eq!(below.count() + above.count(), 0);
     }
 
     #[test]
-    fn test_iter_one() {
-        let mut pairs = one_pair_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        let mut iter = map.iter();
-        assert_eq!(iter.size_hint(), (1, Some(1)));
-        assert_eq!(iter.next(), Some((&"a", &1)));
-        assert_eq!(iter.size_hint(), (0, Some(0)));
-        assert_eq!(iter.next(), None);
+    #[cfg(feature = "std")]
+    fn test_iter_split_at_owned() {
+        let map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        let (mut below, mut above) = map.iter_split_at("c");
+        assert_eq!(below.next(), Some((&"a", &1)));
+        assert_eq!(below.next(), Some((&"b", &2)));
+        assert_eq!(below.next(), None);
+        assert_eq!(above.next(), Some((&"c", &3)));
+        assert_eq!(above.next(), Some((&"d", &4)));
+        assert_eq!(above.next(), None);
+
+        let (below, above) = map.iter_split_at("bb");
+        assert_eq!(below.count(), 2);
+        assert_eq!(above.count(), 2);
     }
 
     #[test]
-    fn test_iter_full() {
+    fn test_count_range() {
         let mut pairs = all_pairs_full();
-        let map = ManagedMap::Borrowed(&mut pairs);
-        let mut iter = map.iter();
-        assert_eq!(iter.size_hint(), (4, Some(4)));
-        assert_eq!(iter.next(), Some((&"a", &1)));
-        assert_eq!(iter.next(), Some((&"b", &2)));
-        assert_eq!(iter.next(), Some((&"c", &3)));
-        assert_eq!(iter.next(), Some((&"d", &4)));
-        assert_eq!(iter.next(), None);
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.count_range("b".."d"), 2);
+        assert_eq!(map.count_range("b"..="d"), 3);
+        assert_eq!(map.count_range::<str, _>((Excluded("a"), Excluded("d"))), 2);
+        assert_eq!(map.count_range("c"..), 2);
+        assert_eq!(map.count_range(.."c"), 2);
+        assert_eq!(map.count_range::<str, _>(..), 4);
+        assert_eq!(map.count_range("b".."b"), 0);
+        assert_eq!(map.count_range("bb".."bc"), 0);
+        assert_eq!(map.count_range("e"..), 0);
+
+        let mut pairs = all_pairs_empty();
+        let map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.count_range::<str, _>(..), 0);
     }
 
     #[test]
-    fn test_iter_mut_full() {
-        let mut pairs = all_pairs_full();
-        let mut map = ManagedMap::Borrowed(&mut pairs);
+    #[cfg(feature = "std")]
+    fn test_count_range_owned() {
+        let map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        assert_eq!(map.count_range("b".."d"), 2);
+        assert_eq!(map.count_range("b"..="d"), 3);
+        assert_eq!(map.count_range::<str, _>((Excluded("a"), Excluded("d"))), 2);
+        assert_eq!(map.count_range::<str, _>(..), 4);
+        assert_eq!(map.count_range("b".."b"), 0);
+        assert_eq!(map.count_range("e"..), 0);
+    }
 
+    #[test]
+    fn test_extract_if() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), Some(("d", 4)),
+                         Some(("e", 5)), None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
         {
-            let mut iter = map.iter_mut();
-            assert_eq!(iter.size_hint(), (0, Some(4)));
-            for (_k, v) in &mut iter {
-                *v += 1;
-            }
-            assert_eq!(iter.size_hint(), (0, Some(0)));
-            // Scope for `iter` ends here so that it can be borrowed
-            // again with the following `iter`.
+            let mut extracted = map.extract_if(|_, value| {
+                *value *= 10;
+                *value % 20 == 0
+            });
+            assert_eq!(extracted.next(), Some(("b", 20)));
+            assert_eq!(extracted.next(), Some(("d", 40)));
+            assert_eq!(extracted.next(), None);
         }
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 10)), Some(("c", 30)), Some(("e", 50)),
+                                  None, None, None]);
+        assert_eq!(map.extract_if(|_, _| false).next(), None);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.extract_if(|_, _| true).count(), 3);
+        assert_eq!(unwrap(&map), [None, None, None, None, None, None]);
+    }
+
+    #[test]
+    fn test_extract_if_dropped() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.extract_if(|key, _| *key != "a").next(), Some(("b", 2)));
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("c", 3)), Some(("d", 4)), None]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_extract_if_pred_panics() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.extract_if(|key, _| match *key {
+                "a" => true,
+                "c" => panic!("predicate failed"),
+                _ => false
+            }).count()
+        }));
+        assert!(result.is_err());
+        assert!(map.is_valid());
+        assert_eq!(unwrap(&map), [Some(("b", 2)), Some(("c", 3)), Some(("d", 4)), None]);
+        assert_eq!(map.insert("a", 5), Ok(None));
+        assert_eq!(map.get("c"), Some(&3));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_extract_if_owned() {
+        let mut map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
         {
-            let mut iter = map.iter();
-            assert_eq!(iter.next(), Some((&"a", &2)));
-            assert_eq!(iter.next(), Some((&"b", &3)));
-            assert_eq!(iter.next(), Some((&"c", &4)));
-            assert_eq!(iter.next(), Some((&"d", &5)));
-            assert_eq!(iter.next(), None);
+            let mut extracted = map.extract_if(|key, value| {
+                *value += 1;
+                *key < "c"
+            });
+            assert_eq!(extracted.next(), Some(("a", 2)));
+            assert_eq!(extracted.next(), Some(("b", 3)));
+            assert_eq!(extracted.next(), None);
         }
+        assert!(map.iter().eq([("c", 4), ("d", 5)].iter().map(|(key, value)| (key, value))));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_extract_if_owned_dropped() {
+        let mut map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        assert_eq!(map.extract_if(|key, _| *key == "b" || *key == "d").next(), Some(("b", 2)));
+        assert!(map.iter().eq([("a", 1), ("c", 3), ("d", 4)].iter().map(|(key, value)| (key, value))));
+        assert_eq!(map.extract_if(|_, _| false).count(), 0);
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.remove_range("b".."d"), 2);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("d", 4)), None, None]);
+        assert_eq!(map.remove_range("a"..="d"), 2);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_remove_range_bounds() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.remove_range::<str, _>((Excluded("a"), Included("c"))), 2);
+        assert_eq!(unwrap(&map), [Some(("a", 1)), Some(("d", 4)), None, None]);
+        assert_eq!(map.remove_range::<str, _>(..), 2);
+        assert_eq!(unwrap(&map), all_pairs_empty());
+    }
+
+    #[test]
+    fn test_remove_range_empty() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.remove_range("b".."b"), 0);
+        assert_eq!(map.remove_range("e"..), 0);
+        assert_eq!(unwrap(&map), all_pairs_full());
+
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        assert_eq!(map.remove_range("a"..), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_remove_range_owned() {
+        let mut map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        assert_eq!(map.remove_range("b".."d"), 2);
+        assert_eq!(map.remove_range::<str, _>((Excluded("a"), Included("d"))), 1);
+        assert_eq!(map.remove_range("b".."b"), 0);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("a"), Some(&1));
+
+        let mut map = ManagedMap::Owned(all_pairs_full().iter().flatten().cloned().collect());
+        assert_eq!(map.remove_range::<str, _>((Included("d"), Excluded("b"))), 0);
+        assert_eq!(map.remove_range::<str, _>((Excluded("b"), Included("b"))), 0);
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.remove_range::<str, _>((Excluded("b"), Unbounded)), 2);
+        assert_eq!(map.remove_range(..="a"), 1);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.remove_range::<str, _>((Unbounded, Unbounded)), 1);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_from_managed_slice() {
+        let mut pairs = [Some(("a", 1)), Some(("b", 2)), None, None];
+        let map = ManagedMap::from(ManagedSlice::from(&mut pairs[..]));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.get("c"), None);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn test_from_managed_slice_unsorted() {
+        let mut pairs = [Some(("b", 2)), None, Some(("a", 1)), None];
+        let _ = ManagedMap::from(ManagedSlice::from(&mut pairs[..]));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_from_managed_slice_owned() {
+        let pairs = ManagedSlice::from([Some(("b", 2)), None, Some(("a", 1)), Some(("b", 3))]);
+        let map = ManagedMap::from(pairs);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.get("b"), Some(&3));
+    }
+
+    #[test]
+    fn test_try_from_pairs_into() {
+        let mut scratch = all_pairs_full();
+        let map = ManagedMap::try_from_pairs_into([("c", 3), ("a", 1), ("b", 2)],
+                                                  &mut scratch).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)), None]);
+    }
+
+    #[test]
+    fn test_try_from_pairs_into_overflow() {
+        let mut scratch = [None; 2];
+        assert_eq!(ManagedMap::try_from_pairs_into([("c", 3), ("a", 1), ("b", 2)],
+                                                   &mut scratch).unwrap_err(),
+                   Error::Full);
+    }
+
+    #[test]
+    fn test_try_from_pairs_into_duplicate() {
+        let mut scratch = all_pairs_empty();
+        assert_eq!(ManagedMap::try_from_pairs_into([("b", 2), ("a", 1), ("b", 3)],
+                                                   &mut scratch).unwrap_err(),
+                   Error::DuplicateKey);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_try_from_pairs_into_vec() {
+        let pairs = [(5, 'e'), (1, 'a')].to_vec();
+        let mut scratch = [None; 2];
+        let map = ManagedMap::try_from_pairs_into(pairs, &mut scratch).unwrap();
+        assert!(map.iter().eq([(&1, &'a'), (&5, &'e')].iter().cloned()));
+    }
+
+    #[test]
+    fn test_map_keys_in_place() {
+        let mut pairs = [Some((1, 'a')), Some((2, 'b')), Some((5, 'c')), None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.map_keys_in_place(|key| key + 10);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&12), Some(&'b'));
+        assert_eq!(unwrap(&map),       [Some((11, 'a')), Some((12, 'b')), Some((15, 'c')), None]);
+    }
+
+    #[test]
+    #[should_panic(expected = "f must preserve the order of the keys")]
+    #[cfg(debug_assertions)]
+    fn test_map_keys_in_place_not_monotonic() {
+        let mut pairs = [Some((1, 'a')), Some((2, 'b')), None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.map_keys_in_place(|key| 10 - key);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_map_keys_in_place_owned() {
+        let mut map = ManagedMap::Owned([(1, 'a'), (2, 'b')].iter().cloned().collect());
+        map.map_keys_in_place(|key| key * 2);
+        assert_eq!(map.get(&2), Some(&'a'));
+        assert_eq!(map.get(&4), Some(&'b'));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "f must preserve the order of the keys")]
+    #[cfg(all(feature = "std", debug_assertions))]
+    fn test_map_keys_in_place_owned_not_monotonic() {
+        let mut map = ManagedMap::Owned([(1, 'a'), (2, 'b')].iter().cloned().collect());
+        map.map_keys_in_place(|_| 0);
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut pairs = [None, Some(("a", 1)), None, Some(("c", 3)), Some(("d", 4)), None];
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.compact();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("c"), Some(&3));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("c", 3)), Some(("d", 4)),
+                                        None, None, None]);
+        map.compact();
+        assert_eq!(map.insert("b", 2), Ok(None));
+        assert_eq!(unwrap(&map),       [Some(("a", 1)), Some(("b", 2)), Some(("c", 3)),
+                                        Some(("d", 4)), None, None]);
+    }
+
+    #[test]
+    fn test_compact_packed() {
+        let mut pairs = all_pairs_full();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.compact();
+        assert_eq!(unwrap(&map),       all_pairs_full());
+        let mut pairs = all_pairs_empty();
+        let mut map = ManagedMap::from(&mut pairs[..]);
+        map.compact();
+        assert_eq!(unwrap(&map),       all_pairs_empty());
     }
 }
@@ -0,0 +1,302 @@
+//! A borrowed map ordered by a comparator instead of `Ord`.
+//!
+//! See the documentation of [`ManagedMapBy`] for details.
+//!
+//! [`ManagedMapBy`]: struct.ManagedMapBy.html
+use core::fmt;
+use core::mem;
+use core::cmp::Ordering;
+
+use error::CapacityError;
+use map::{Iter, IterMut, occupied, occupied_mut, shift_insert, shift_remove};
+use util::search_packed_by;
+
+/// A map backed by a borrowed slice of pairs, like the borrowed variant of `ManagedMap`,
+/// that orders its keys by a comparator instead of their `Ord` implementation.
+///
+/// The entries are stored at the front of the slice in the order given by the comparator,
+/// and the comparator is used for every lookup and insertion, so keys that it considers equal
+/// are the same key. There is no owned variant, since `BTreeMap` cannot be given a comparator;
+/// an owned map with a custom ordering needs a key newtype that implements `Ord`, as described
+/// in the documentation of `ManagedMap`.
+///
+/// For example, case-insensitive string keys:
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use managed::ManagedMapBy;
+/// fn case_insensitive(lhs: &&str, rhs: &&str) -> Ordering {
+///     let lhs = lhs.bytes().map(|c| c.to_ascii_lowercase());
+///     let rhs = rhs.bytes().map(|c| c.to_ascii_lowercase());
+///     lhs.cmp(rhs)
+/// }
+///
+/// let mut pairs = [None; 4];
+/// let mut map = ManagedMapBy::new(&mut pairs[..], case_insensitive);
+/// map.insert("Foo", 1).unwrap();
+/// assert_eq!(map.get(&"FOO"), Some(&1));
+/// ```
+pub struct ManagedMapBy<'a, K: 'a, V: 'a, C> {
+    pairs: &'a mut [Option<(K, V)>],
+    len:   usize,
+    cmp:   C,
+}
+
+/// Formats the entries of the map like `ManagedMap` does.
+impl<'a, K: 'a, V: 'a, C> fmt::Debug for ManagedMapBy<'a, K, V, C>
+        where K: fmt::Debug, V: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(occupied(self.pairs, self.len).iter().flatten()
+                                  .map(|(key, value)| (key, value))).finish()
+    }
+}
+
+impl<'a, K: 'a, V: 'a, C> ManagedMapBy<'a, K, V, C>
+        where C: Fn(&K, &K) -> Ordering {
+    /// Creates a map from a slice whose entries are packed at the front in the order given
+    /// by `cmp`, counting the entries, which takes O(n) time.
+    ///
+    /// The order of the entries is only checked in debug builds.
+    pub fn new(pairs: &'a mut [Option<(K, V)>], cmp: C) -> Self {
+        let len = pairs.iter().take_while(|item| item.is_some()).count();
+        let map = ManagedMapBy { pairs, len, cmp };
+        debug_assert!(map.is_valid(), "pairs must be sorted by `cmp` and packed at the front");
+        map
+    }
+
+    /// Consumes the map, returning the backing slice and the number of entries in it.
+    pub fn into_inner(self) -> (&'a mut [Option<(K, V)>], usize) {
+        (self.pairs, self.len)
+    }
+
+    fn search(&self, key: &K) -> Result<usize, usize> {
+        let cmp = &self.cmp;
+        search_packed_by(occupied(self.pairs, self.len), |entry| cmp(entry, key))
+    }
+
+    /// Removes all entries from the map.
+    pub fn clear(&mut self) {
+        for item in occupied_mut(self.pairs, self.len).iter_mut() {
+            *item = None
+        }
+        self.len = 0
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        match self.search(key) {
+            Ok(idx) => self.pairs[idx].as_ref().map(|(_, value)| value),
+            Err(_) => None
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self.search(key) {
+            Ok(idx) => self.pairs[idx].as_mut().map(|&mut (_, ref mut value)| value),
+            Err(_) => None
+        }
+    }
+
+    /// Returns the key stored in the map along with its value, which may differ from `key`
+    /// while still comparing equal to it.
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        match self.search(key) {
+            Ok(idx) => self.pairs[idx].as_ref().map(|(key, value)| (key, value)),
+            Err(_) => None
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Inserts a pair, returning the value it replaced, if any.
+    ///
+    /// As for a borrowed `ManagedMap`, a replaced entry takes both the new key and the new
+    /// value. Returns the pair in a `CapacityError` if the key is not present and the map
+    /// is full. This takes O(log n + m) time, where m is the number of entries after the key,
+    /// which are shifted by one slot.
+    pub fn insert(&mut self, key: K, new_value: V) -> Result<Option<V>, CapacityError<(K, V)>> {
+        match self.search(&key) {
+            Ok(idx) => {
+                match self.pairs[idx] {
+                    Some(ref mut pair) => {
+                        let (_key, value) = mem::replace(pair, (key, new_value));
+                        Ok(Some(value))
+                    }
+                    // `search` only finds a key in an occupied slot.
+                    None => Err(CapacityError::new((key, new_value)))
+                }
+            }
+            Err(idx) => {
+                match shift_insert(self.pairs, self.len, idx, (key, new_value)) {
+                    Ok(_) => {
+                        self.len += 1;
+                        Ok(None)
+                    }
+                    Err(pair) => Err(CapacityError::new(pair))
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        match self.search(key) {
+            Ok(idx) => {
+                let (_key, value) = shift_remove(self.pairs, self.len, idx)?;
+                self.len -= 1;
+                Some(value)
+            }
+            Err(_) => None
+        }
+    }
+
+    /// ManagedMapBy contains no elements?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of elements in the ManagedMapBy.
+    pub fn len(&self) -> usize {
+        occupied(self.pairs, self.len).len()
+    }
+
+    /// Checks whether no more entries with new keys can be inserted, which is the case when
+    /// every slot of the backing slice is occupied.
+    pub fn is_full(&self) -> bool {
+        self.len >= self.pairs.len()
+    }
+
+    /// Checks whether the map upholds its invariant.
+    ///
+    /// The occupied slots must be strictly sorted by the comparator, and the rest must be
+    /// free. This can only be broken by a comparator that is not a total order, or by
+    /// `new` in a release build.
+    pub fn is_valid(&self) -> bool {
+        let (entries, rest) = self.pairs.split_at(occupied(self.pairs, self.len).len());
+        entries.iter().all(|item| item.is_some()) &&
+            rest.iter().all(|item| item.is_none()) &&
+            entries.windows(2).all(|window| {
+                match (&window[0], &window[1]) {
+                    (&Some((ref lhs, _)), &Some((ref rhs, _))) =>
+                        (self.cmp)(lhs, rhs) == Ordering::Less,
+                    _ => false
+                }
+            })
+    }
+
+    /// Returns an iterator over the entries of the map, in the order given by the comparator.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::Borrowed(occupied(self.pairs, self.len).iter())
+    }
+
+    /// Returns an iterator over the entries of the map, with mutable references to the values,
+    /// in the order given by the comparator.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut::Borrowed(occupied_mut(self.pairs, self.len).iter_mut())
+    }
+}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::ManagedMapBy;
+    use core::cmp::Ordering;
+    use error::CapacityError;
+
+    fn case_insensitive(lhs: &&str, rhs: &&str) -> Ordering {
+        let lhs = lhs.bytes().map(|c| c.to_ascii_lowercase());
+        let rhs = rhs.bytes().map(|c| c.to_ascii_lowercase());
+        lhs.cmp(rhs)
+    }
+
+    #[test]
+    fn test_insert_case_insensitive() {
+        let mut pairs = [None; 4];
+        let mut map = ManagedMapBy::new(&mut pairs[..], case_insensitive);
+        assert_eq!(map.insert("banana", 2), Ok(None));
+        assert_eq!(map.insert("Cherry", 3), Ok(None));
+        assert_eq!(map.insert("APPLE", 1), Ok(None));
+        // `Ord` would put the uppercase keys first.
+        assert!(map.iter().eq([("APPLE", 1), ("banana", 2), ("Cherry", 3)].iter()
+                                  .map(|(key, value)| (key, value))));
+        assert_eq!(map.insert("Banana", 20), Ok(Some(2)));
+        assert_eq!(map.len(), 3);
+        assert!(map.is_valid());
+        let (pairs, len) = map.into_inner();
+        assert_eq!(len, 3);
+        assert_eq!(pairs, [Some(("APPLE", 1)), Some(("Banana", 20)), Some(("Cherry", 3)), None]);
+    }
+
+    #[test]
+    fn test_get_case_insensitive() {
+        let mut pairs = [None; 4];
+        let mut map = ManagedMapBy::new(&mut pairs[..], case_insensitive);
+        assert_eq!(map.insert("Foo", 1), Ok(None));
+        assert_eq!(map.get(&"foo"), Some(&1));
+        assert_eq!(map.get(&"FOO"), Some(&1));
+        assert_eq!(map.get(&"bar"), None);
+        assert_eq!(map.get_key_value(&"fOO"), Some((&"Foo", &1)));
+        assert!(map.contains_key(&"fOo"));
+        *map.get_mut(&"FOO").unwrap() += 1;
+        assert_eq!(map.get(&"Foo"), Some(&2));
+        assert_eq!(map.get_mut(&"bar"), None);
+    }
+
+    #[test]
+    fn test_remove_case_insensitive() {
+        let mut pairs = [Some(("a", 1)), Some(("B", 2)), Some(("c", 3)), None];
+        let mut map = ManagedMapBy::new(&mut pairs[..], case_insensitive);
+        assert_eq!(map.remove(&"b"), Some(2));
+        assert_eq!(map.remove(&"b"), None);
+        assert_eq!(map.len(), 2);
+        assert!(map.is_valid());
+        assert_eq!(map.get(&"C"), Some(&3));
+        let (pairs, _) = map.into_inner();
+        assert_eq!(pairs, [Some(("a", 1)), Some(("c", 3)), None, None]);
+    }
+
+    #[test]
+    fn test_insert_full() {
+        let mut pairs = [None; 2];
+        let mut map = ManagedMapBy::new(&mut pairs[..], case_insensitive);
+        assert_eq!(map.insert("a", 1), Ok(None));
+        assert_eq!(map.insert("b", 2), Ok(None));
+        assert!(map.is_full());
+        assert_eq!(map.insert("c", 3), Err(CapacityError::new(("c", 3))));
+        assert_eq!(map.insert("A", 10), Ok(Some(1)));
+        map.clear();
+        assert!(map.is_empty());
+        assert!(!map.is_full());
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut pairs = [Some(("a", 1)), Some(("B", 2)), None];
+        let mut map = ManagedMapBy::new(&mut pairs[..], case_insensitive);
+        for (_, value) in map.iter_mut() {
+            *value *= 10
+        }
+        assert!(map.iter().eq([("a", 10), ("B", 20)].iter().map(|(key, value)| (key, value))));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        let mut pairs = [Some(("a", 1)), Some(("A", 2)), None];
+        let map = ManagedMapBy { pairs: &mut pairs[..], len: 2, cmp: case_insensitive };
+        assert!(!map.is_valid());
+        let mut pairs = [Some(("b", 1)), Some(("a", 2)), None];
+        let map = ManagedMapBy { pairs: &mut pairs[..], len: 2, cmp: case_insensitive };
+        assert!(!map.is_valid());
+        let mut pairs = [Some(("b", 1)), None, Some(("c", 2))];
+        let map = ManagedMapBy { pairs: &mut pairs[..], len: 1, cmp: case_insensitive };
+        assert!(!map.is_valid());
+    }
+
+    #[test]
+    #[should_panic(expected = "pairs must be sorted")]
+    #[cfg(debug_assertions)]
+    fn test_new_unsorted() {
+        let mut pairs = [Some(("b", 1)), Some(("A", 2))];
+        let _ = ManagedMapBy::new(&mut pairs[..], case_insensitive);
+    }
+}
@@ -1,4 +1,6 @@
 use core::ops::{Deref, DerefMut};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
 use core::fmt;
 
 #[cfg(feature = "std")]
@@ -28,6 +30,9 @@ use alloc::vec::Vec;
 /// Note that a `Vec<T>` converted into an `Into<Managed<'a, [T]>>` gets transformed
 /// into a boxed slice, and can no longer be resized. See also
 /// [ManagedSlice](enum.ManagedSlice.html), which does not have this drawback.
+///
+/// Comparison and hashing only look at the object itself, so a borrowed and an owned
+/// object with equal values are interchangeable when used e.g. as keys of a map.
 pub enum Managed<'a, T: 'a + ?Sized> {
     /// Borrowed variant.
     Borrowed(&'a mut T),
@@ -47,6 +52,54 @@ impl<'a, T: 'a + ?Sized> fmt::Debug for Managed<'a, T>
     }
 }
 
+/// Clones the managed object into a new owned object.
+///
+/// A borrowed object cannot be cloned without giving up exclusive access, so both variants
+/// are cloned into the owned variant. As such, this is only available with the `std`
+/// or `alloc` feature enabled.
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, T: Clone + 'a> Clone for Managed<'a, T> {
+    fn clone(&self) -> Self {
+        Managed::Owned(Box::new((**self).clone()))
+    }
+}
+
+/// Creates an owned object holding `T::default()`.
+///
+/// This is only available with the `std` or `alloc` feature enabled.
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, T: Default + 'a> Default for Managed<'a, T> {
+    fn default() -> Self {
+        Managed::Owned(Box::default())
+    }
+}
+
+impl<'a, T: 'a + ?Sized + PartialEq> PartialEq for Managed<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<'a, T: 'a + ?Sized + Eq> Eq for Managed<'a, T> {}
+
+impl<'a, T: 'a + ?Sized + PartialOrd> PartialOrd for Managed<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<'a, T: 'a + ?Sized + Ord> Ord for Managed<'a, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<'a, T: 'a + ?Sized + Hash> Hash for Managed<'a, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
 impl<'a, T: 'a + ?Sized> From<&'a mut T> for Managed<'a, T> {
     fn from(value: &'a mut T) -> Self {
         Managed::Borrowed(value)
@@ -67,6 +120,35 @@ impl<'a, T: 'a> From<Vec<T>> for Managed<'a, [T]> {
     }
 }
 
+impl<'a, T: 'a + ?Sized> Managed<'a, T> {
+    /// Checks whether the object is borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            Managed::Borrowed(_) => true,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            Managed::Owned(_) => false
+        }
+    }
+
+    /// Checks whether the object is owned.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+}
+
+impl<'a, T: Clone + 'a> Managed<'a, T> {
+    /// Returns the owned object, or a clone of the borrowed object.
+    ///
+    /// An owned object is moved out of its box and never cloned.
+    pub fn into_owned(self) -> T {
+        match self {
+            Managed::Borrowed(value) => value.clone(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            Managed::Owned(value) => *value
+        }
+    }
+}
+
 impl<'a, T: 'a + ?Sized> Deref for Managed<'a, T> {
     type Target = T;
 
@@ -88,3 +170,181 @@ impl<'a, T: 'a + ?Sized> DerefMut for Managed<'a, T> {
         }
     }
 }
+
+impl<'a, T: 'a + ?Sized> AsRef<T> for Managed<'a, T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, T: 'a + ?Sized> AsMut<T> for Managed<'a, T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::Managed;
+    #[cfg(feature = "std")]
+    use std::boxed::Box;
+    #[cfg(feature = "std")]
+    use std::string::String;
+    #[cfg(feature = "std")]
+    use std::collections::hash_map::DefaultHasher;
+    #[cfg(feature = "std")]
+    use core::hash::{Hash, Hasher};
+
+    #[cfg(feature = "std")]
+    fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn len_of<S: AsRef<str> + ?Sized>(s: &S) -> usize {
+        s.as_ref().len()
+    }
+
+    fn zero<T: AsMut<[u8]> + ?Sized>(buf: &mut T) {
+        for byte in buf.as_mut() {
+            *byte = 0;
+        }
+    }
+
+    #[test]
+    fn test_cmp_borrowed() {
+        let (mut a, mut b, mut c) = (1, 1, 2);
+        let (a, b, c) = (Managed::Borrowed(&mut a), Managed::Borrowed(&mut b),
+                         Managed::Borrowed(&mut c));
+        assert!(a == b);
+        assert!(a != c);
+        assert!(a < c);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_cmp_hash_cross_variant() {
+        let mut value = String::from("foo");
+        let borrowed: Managed<str> = Managed::Borrowed(&mut value[..]);
+        let owned: Managed<str> = Managed::Owned(Box::from("foo"));
+        let other: Managed<str> = Managed::Owned(Box::from("bar"));
+        assert!(borrowed == owned);
+        assert_eq!(borrowed.cmp(&owned), core::cmp::Ordering::Equal);
+        assert!(other < borrowed);
+        assert_eq!(hash_of(&borrowed), hash_of(&owned));
+        assert_eq!(hash_of(&borrowed), hash_of("foo"));
+    }
+
+    #[test]
+    fn test_as_ref_as_mut_borrowed() {
+        let mut text = *b"abc";
+        {
+            let mut managed: Managed<[u8]> = Managed::Borrowed(&mut text[..]);
+            assert_eq!(AsRef::<[u8]>::as_ref(&managed), b"abc");
+            zero(&mut managed);
+        }
+        assert_eq!(text, [0; 3]);
+
+        let mut owned_text = *b"hello";
+        let managed: Managed<str> =
+            Managed::Borrowed(core::str::from_utf8_mut(&mut owned_text).unwrap());
+        assert_eq!(len_of(&managed), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_as_ref_as_mut_owned() {
+        fn push_bang<S: AsMut<String>>(mut s: S) -> S {
+            s.as_mut().push('!');
+            s
+        }
+
+        let managed: Managed<String> = Managed::Owned(Box::new(String::from("hi")));
+        let managed = push_bang(managed);
+        let inner: &String = managed.as_ref();
+        assert_eq!(len_of(inner), 3);
+        assert_eq!(&**managed, "hi!");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_clone_borrowed() {
+        let mut value = 1;
+        let mut managed = Managed::Borrowed(&mut value);
+        let clone = managed.clone();
+        *managed = 2;
+        match clone {
+            Managed::Owned(ref inner) => assert_eq!(**inner, 1),
+            _ => panic!("expected an owned clone")
+        }
+        assert_eq!(value, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_clone_owned() {
+        let mut managed = Managed::Owned(Box::new(1));
+        let clone = managed.clone();
+        *managed = 2;
+        assert_eq!(*clone, 1);
+        assert_eq!(*managed, 2);
+    }
+
+    #[test]
+    fn test_is_borrowed() {
+        let mut value = 1;
+        let managed = Managed::Borrowed(&mut value);
+        assert!(managed.is_borrowed());
+        assert!(!managed.is_owned());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_is_owned() {
+        let managed: Managed<str> = Managed::Owned(Box::from("foo"));
+        assert!(managed.is_owned());
+        assert!(!managed.is_borrowed());
+    }
+
+    #[test]
+    fn test_into_owned_borrowed() {
+        let mut value = 1;
+        let owned = Managed::Borrowed(&mut value).into_owned();
+        assert_eq!(owned, 1);
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_into_owned_owned() {
+        struct NoClone(i32);
+
+        impl Clone for NoClone {
+            fn clone(&self) -> Self {
+                panic!("the owned object must not be cloned")
+            }
+        }
+
+        let managed = Managed::Owned(Box::new(NoClone(1)));
+        assert_eq!(managed.into_owned().0, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_default() {
+        let managed = Managed::<i32>::default();
+        assert_eq!(*managed, 0);
+        match managed {
+            Managed::Owned(_) => (),
+            _ => panic!("expected an owned object")
+        }
+
+        #[derive(Default)]
+        struct Holder<'a> {
+            name: Managed<'a, String>,
+        }
+        assert_eq!(&**Holder::default().name, "");
+    }
+}
@@ -0,0 +1,240 @@
+use core::ops::Deref;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::rc::Rc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::sync::Arc;
+#[cfg(all(feature = "serde", feature = "std"))]
+use std::vec::Vec;
+#[cfg(all(feature = "serde", feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+#[cfg(all(feature = "serde", any(feature = "std", feature = "alloc")))]
+use serde::de::SeqAccess;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
+/// A managed shared object.
+///
+/// This enum can be used to represent shared, read-only access to objects. Unlike
+/// [Managed](enum.Managed.html), which represents exclusive access, it either borrows the object
+/// immutably or holds a reference-counted pointer to it, and thus only implements `Deref`.
+///
+/// The variants other than `Borrowed` are only available when the corresponding
+/// feature is opted in.
+///
+/// A function that requires a shared object should be generic over an `Into<ManagedShared<'a, T>>`
+/// argument; then, it will be possible to pass either an `Rc<T>`, an `Arc<T>`, or a `&'a T`
+/// without any conversion at the call site.
+pub enum ManagedShared<'a, T: 'a + ?Sized> {
+    /// Borrowed variant.
+    Borrowed(&'a T),
+    /// Single-threaded reference-counted variant, only available with the `std` or `alloc`
+    /// feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Rc(Rc<T>),
+    /// Thread-safe reference-counted variant, only available with the `std` or `alloc`
+    /// feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Arc(Arc<T>)
+}
+
+impl<'a, T: 'a + ?Sized> fmt::Debug for ManagedShared<'a, T>
+        where T: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManagedShared::Borrowed(x) => write!(f, "Borrowed({:?})", x),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedShared::Rc(x)       => write!(f, "Rc({:?})", x),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedShared::Arc(x)      => write!(f, "Arc({:?})", x)
+        }
+    }
+}
+
+impl<'a, T: 'a + ?Sized> Clone for ManagedShared<'a, T> {
+    fn clone(&self) -> Self {
+        match self {
+            ManagedShared::Borrowed(value) => ManagedShared::Borrowed(value),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedShared::Rc(value) => ManagedShared::Rc(value.clone()),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedShared::Arc(value) => ManagedShared::Arc(value.clone())
+        }
+    }
+}
+
+impl<'a, T: 'a + ?Sized> From<&'a T> for ManagedShared<'a, T> {
+    fn from(value: &'a T) -> Self {
+        ManagedShared::Borrowed(value)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, T: 'a + ?Sized> From<Rc<T>> for ManagedShared<'a, T> {
+    fn from(value: Rc<T>) -> Self {
+        ManagedShared::Rc(value)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, T: 'a + ?Sized> From<Arc<T>> for ManagedShared<'a, T> {
+    fn from(value: Arc<T>) -> Self {
+        ManagedShared::Arc(value)
+    }
+}
+
+impl<'a, T: 'a + ?Sized> Deref for ManagedShared<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            ManagedShared::Borrowed(value) => value,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedShared::Rc(value) => value,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedShared::Arc(value) => value
+        }
+    }
+}
+
+/// Serializes the bytes with `serialize_bytes`.
+#[cfg(feature = "serde")]
+impl<'a> Serialize for ManagedShared<'a, [u8]> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+/// Deserializes bytes, borrowing them from the input if the format allows it.
+///
+/// Unlike a managed slice, which needs exclusive access, a shared object can borrow from
+/// the input without copying it. Otherwise, the bytes are copied into an `Rc`, which is only
+/// possible with the `std` or `alloc` feature enabled; without them, such input is rejected.
+#[cfg(feature = "serde")]
+impl<'de: 'a, 'a> Deserialize<'de> for ManagedShared<'a, [u8]> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = ManagedShared<'de, [u8]>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a byte array")
+            }
+
+            fn visit_borrowed_bytes<E: de::Error>(self, value: &'de [u8]) -> Result<Self::Value, E> {
+                Ok(ManagedShared::Borrowed(value))
+            }
+
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+                Ok(ManagedShared::Rc(Rc::from(value)))
+            }
+
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte)
+                }
+                Ok(ManagedShared::Rc(Rc::from(bytes)))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::ManagedShared;
+    #[cfg(feature = "std")]
+    use std::rc::Rc;
+    #[cfg(feature = "std")]
+    use std::sync::Arc;
+
+    #[test]
+    fn test_borrowed() {
+        let value = 5;
+        let shared = ManagedShared::from(&value);
+        let clone = shared.clone();
+        assert_eq!(*shared, 5);
+        assert_eq!(*clone, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_rc() {
+        let rc = Rc::new([1, 2, 3]);
+        let shared: ManagedShared<[u8; 3]> = ManagedShared::from(rc.clone());
+        assert_eq!(shared.len(), 3);
+        assert_eq!(shared[1], 2);
+        let clone = shared.clone();
+        assert_eq!(Rc::strong_count(&rc), 3);
+        drop(clone);
+        drop(shared);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_deserialize_borrowed() {
+        use serde::Deserialize;
+        use serde::de::value::{BorrowedBytesDeserializer, Error};
+
+        let input = [1u8, 2, 3];
+        let deserializer = BorrowedBytesDeserializer::<Error>::new(&input);
+        let shared = ManagedShared::<[u8]>::deserialize(deserializer).unwrap();
+        match shared {
+            ManagedShared::Borrowed(bytes) => assert_eq!(bytes.as_ptr(), input.as_ptr()),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "std"))]
+    fn test_deserialize_postcard() {
+        let input = ManagedShared::Borrowed(&[1u8, 2, 255][..]);
+        let encoded = postcard::to_allocvec(&input).unwrap();
+        assert_eq!(encoded, [3, 1, 2, 255]);
+        let shared: ManagedShared<[u8]> = postcard::from_bytes(&encoded).unwrap();
+        match shared {
+            ManagedShared::Borrowed(bytes) => {
+                assert_eq!(bytes, &[1, 2, 255]);
+                assert_eq!(bytes.as_ptr(), encoded[1..].as_ptr())
+            },
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "std"))]
+    fn test_deserialize_copied() {
+        use serde::Deserialize;
+        use serde::de::value::{BytesDeserializer, Error};
+
+        let input = [1u8, 2, 3];
+        let deserializer = BytesDeserializer::<Error>::new(&input);
+        let shared = ManagedShared::<[u8]>::deserialize(deserializer).unwrap();
+        match shared {
+            ManagedShared::Rc(ref bytes) => assert_eq!(&bytes[..], &input[..]),
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_arc() {
+        let shared: ManagedShared<str> = ManagedShared::from(Arc::<str>::from("hello"));
+        assert_eq!(&*shared, "hello");
+    }
+}
@@ -1,5 +1,15 @@
-use core::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut, Index, IndexMut, Range};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
 use core::fmt;
+use core::slice::{self, SliceIndex};
+use core::mem::{self, MaybeUninit};
+use core::iter::Enumerate;
+use core::convert::TryFrom;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::iter::FromIterator;
+
+use error::CapacityError;
 
 #[cfg(feature = "std")]
 use std::boxed::Box;
@@ -9,6 +19,10 @@ use alloc::boxed::Box;
 use std::vec::Vec;
 #[cfg(all(feature = "alloc", not(feature = "std")))]
 use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::TryReserveError;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::collections::TryReserveError;
 
 /// A managed slice.
 ///
@@ -25,6 +39,19 @@ use alloc::vec::Vec;
 /// argument; then, it will be possible to pass either a `Vec<T>`, or a `&'a mut [T]`
 /// without any conversion at the call site.
 ///
+/// Equality, ordering and hashing are those of the underlying slice, regardless of the variant.
+///
+/// There is no owned variant for fixed-capacity vectors such as `arrayvec::ArrayVec<T, N>`,
+/// since it would need a capacity parameter on this enum, and a feature adding a variant
+/// would break exhaustive matches in other crates. The elements of such a vector can still
+/// be lent out with [`from_mut`](#method.from_mut), though the managed slice cannot grow it.
+///
+/// A managed slice of bytes is not deserialized by borrowing from the input, because
+/// deserializers only lend out shared references to it, and the `Borrowed` variant needs
+/// a mutable one. To deserialize bytes without copying them, use
+/// [ManagedShared](enum.ManagedShared.html)`<[u8]>` instead, and to copy them into an owned
+/// buffer, use [ManagedBytes](struct.ManagedBytes.html).
+///
 /// See also [Managed](enum.Managed.html).
 pub enum ManagedSlice<'a, T: 'a> {
     /// Borrowed variant.
@@ -34,23 +61,69 @@ pub enum ManagedSlice<'a, T: 'a> {
     Owned(Vec<T>)
 }
 
+/// Formats the contents of the slice, regardless of the variant.
+///
+/// The alternate form (`{:#?}`) also shows the variant.
 impl<'a, T: 'a> fmt::Debug for ManagedSlice<'a, T>
         where T: fmt::Debug {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ManagedSlice::Borrowed(x) => write!(f, "Borrowed({:?})", x),
+            ManagedSlice::Borrowed(x) if f.alternate() => f.debug_tuple("Borrowed").field(x).finish(),
             #[cfg(any(feature = "std", feature = "alloc"))]
-            ManagedSlice::Owned(x)    => write!(f, "Owned({:?})", x)
+            ManagedSlice::Owned(x) if f.alternate()    => f.debug_tuple("Owned").field(x).finish(),
+            _ => (**self).fmt(f)
         }
     }
 }
 
+impl<'a, T: 'a + PartialEq> PartialEq for ManagedSlice<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<'a, T: 'a + Eq> Eq for ManagedSlice<'a, T> {}
+
+impl<'a, T: 'a + PartialOrd> PartialOrd for ManagedSlice<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<'a, T: 'a + Ord> Ord for ManagedSlice<'a, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<'a, T: 'a + Hash> Hash for ManagedSlice<'a, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
 impl<'a, T: 'a> From<&'a mut [T]> for ManagedSlice<'a, T> {
     fn from(value: &'a mut [T]) -> Self {
         ManagedSlice::Borrowed(value)
     }
 }
 
+/// Borrows the first `len` elements of a slice, e.g. the initialized part of a larger buffer.
+///
+/// Returns the slice and `len` in a `CapacityError` if `len` is greater than the length
+/// of the slice.
+impl<'a, T: 'a> TryFrom<(&'a mut [T], usize)> for ManagedSlice<'a, T> {
+    type Error = CapacityError<(&'a mut [T], usize)>;
+
+    fn try_from((value, len): (&'a mut [T], usize)) -> Result<Self, Self::Error> {
+        if len > value.len() {
+            return Err(CapacityError::new((value, len)))
+        }
+        Ok(ManagedSlice::Borrowed(&mut value[..len]))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
 macro_rules! from_unboxed_slice {
     ($n:expr) => (
         impl<'a, T> From<[T; $n]> for ManagedSlice<'a, T> {
@@ -77,6 +150,58 @@ impl<'a, T: 'a> From<Vec<T>> for ManagedSlice<'a, T> {
     }
 }
 
+/// Borrows the elements of a `Vec` without moving it, unlike `From<Vec<T>>`.
+///
+/// The resulting slice is borrowed, so it cannot grow the `Vec`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, T: 'a> From<&'a mut Vec<T>> for ManagedSlice<'a, T> {
+    fn from(value: &'a mut Vec<T>) -> Self {
+        ManagedSlice::Borrowed(value.as_mut_slice())
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, T: 'a> FromIterator<T> for ManagedSlice<'a, T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        ManagedSlice::Owned(iter.into_iter().collect())
+    }
+}
+
+/// An error returned by `ManagedSlice::append` and `ManagedSlice::append_cloned`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendError {
+    /// The slice to append to is borrowed, and cannot grow.
+    BorrowedDestination,
+    /// The slice to append is borrowed, so its elements cannot be moved out of it.
+    BorrowedSource,
+}
+
+impl fmt::Display for AppendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppendError::BorrowedDestination => write!(f, "cannot append to a borrowed slice"),
+            AppendError::BorrowedSource => write!(f, "cannot move out of a borrowed slice"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AppendError {}
+
+/// An error returned by `ManagedSlice::set_len` if the slice is borrowed, since the length
+/// of a borrowed slice cannot change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResizeError;
+
+impl fmt::Display for ResizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot resize a borrowed slice")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ResizeError {}
+
 impl<'a, T: 'a> Deref for ManagedSlice<'a, T> {
     type Target = [T];
 
@@ -98,3 +223,1478 @@ impl<'a, T: 'a> DerefMut for ManagedSlice<'a, T> {
         }
     }
 }
+
+impl<'a, T: 'a, I: SliceIndex<[T]>> Index<I> for ManagedSlice<'a, T> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &(**self)[index]
+    }
+}
+
+impl<'a, T: 'a, I: SliceIndex<[T]>> IndexMut<I> for ManagedSlice<'a, T> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut (**self)[index]
+    }
+}
+
+impl<'a, 'b, T: 'a> IntoIterator for &'b ManagedSlice<'a, T> {
+    type Item = &'b T;
+    type IntoIter = slice::Iter<'b, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, 'b, T: 'a> IntoIterator for &'b mut ManagedSlice<'a, T> {
+    type Item = &'b mut T;
+    type IntoIter = slice::IterMut<'b, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> ManagedSlice<'static, T> {
+    /// Returns an empty borrowed slice.
+    ///
+    /// This does not require allocation and can be used in `const` and `static` initializers,
+    /// e.g. as a placeholder until the real storage is assigned.
+    pub const fn empty() -> Self {
+        ManagedSlice::Borrowed(&mut [])
+    }
+}
+
+impl<'a, T: 'a> ManagedSlice<'a, T> {
+    /// Borrows anything that can be viewed as a mutable slice, such as an array or a `Vec`.
+    pub fn from_mut<S: AsMut<[T]> + ?Sized>(source: &'a mut S) -> Self {
+        ManagedSlice::Borrowed(source.as_mut())
+    }
+
+    /// Checks whether the slice is borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            ManagedSlice::Borrowed(_) => true,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            ManagedSlice::Owned(_) => false
+        }
+    }
+
+    /// Checks whether the slice is owned.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    /// Returns an iterator over the slice.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        (**self).iter()
+    }
+
+    /// Returns an iterator that allows modifying each element.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        (**self).iter_mut()
+    }
+
+    /// Returns an iterator that allows modifying each element, along with its index.
+    ///
+    /// This is the same as `iter_mut().enumerate()`.
+    pub fn enumerate_mut(&mut self) -> Enumerate<slice::IterMut<'_, T>> {
+        (**self).iter_mut().enumerate()
+    }
+
+    /// Returns the first element of the slice, or `None` if it is empty.
+    pub fn first(&self) -> Option<&T> {
+        (**self).first()
+    }
+
+    /// Returns a mutable reference to the first element of the slice, or `None` if it is empty.
+    pub fn first_mut(&mut self) -> Option<&mut T> {
+        (**self).first_mut()
+    }
+
+    /// Returns the last element of the slice, or `None` if it is empty.
+    pub fn last(&self) -> Option<&T> {
+        (**self).last()
+    }
+
+    /// Returns a mutable reference to the last element of the slice, or `None` if it is empty.
+    pub fn last_mut(&mut self) -> Option<&mut T> {
+        (**self).last_mut()
+    }
+
+    /// Returns an iterator over `size` elements of the slice at a time.
+    pub fn chunks(&self, size: usize) -> slice::Chunks<'_, T> {
+        (**self).chunks(size)
+    }
+
+    /// Returns an iterator over `size` elements of the slice at a time, as mutable slices.
+    pub fn chunks_mut(&mut self, size: usize) -> slice::ChunksMut<'_, T> {
+        (**self).chunks_mut(size)
+    }
+
+    /// Returns an iterator over all contiguous windows of length `size`.
+    pub fn windows(&self, size: usize) -> slice::Windows<'_, T> {
+        (**self).windows(size)
+    }
+
+    /// Returns `true` if the slice contains an element equal to `x`.
+    pub fn contains(&self, x: &T) -> bool
+        where T: PartialEq
+    {
+        (**self).contains(x)
+    }
+
+    /// Binary searches this sorted slice for a given element.
+    ///
+    /// See `slice::binary_search` for the meaning of the returned value.
+    pub fn binary_search(&self, x: &T) -> Result<usize, usize>
+        where T: Ord
+    {
+        (**self).binary_search(x)
+    }
+
+    /// Binary searches this sorted slice with a comparator function.
+    pub fn binary_search_by<F>(&self, f: F) -> Result<usize, usize>
+        where F: FnMut(&T) -> Ordering
+    {
+        (**self).binary_search_by(f)
+    }
+
+    /// Binary searches this sorted slice with a key extraction function.
+    pub fn binary_search_by_key<B, F>(&self, b: &B, f: F) -> Result<usize, usize>
+        where F: FnMut(&T) -> B, B: Ord
+    {
+        (**self).binary_search_by_key(b, f)
+    }
+
+    /// Sorts the slice, preserving the order of equal elements.
+    ///
+    /// This is only available with the `std` or `alloc` feature enabled, since the stable
+    /// sort allocates; otherwise, use `sort_unstable`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn sort(&mut self)
+        where T: Ord
+    {
+        (**self).sort()
+    }
+
+    /// Sorts the slice with a comparator function, preserving the order of equal elements.
+    ///
+    /// This is only available with the `std` or `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn sort_by<F>(&mut self, compare: F)
+        where F: FnMut(&T, &T) -> Ordering
+    {
+        (**self).sort_by(compare)
+    }
+
+    /// Sorts the slice without preserving the order of equal elements, and without allocating.
+    pub fn sort_unstable(&mut self)
+        where T: Ord
+    {
+        (**self).sort_unstable()
+    }
+
+    /// Sorts the slice with a comparator function, without preserving the order
+    /// of equal elements, and without allocating.
+    pub fn sort_unstable_by<F>(&mut self, compare: F)
+        where F: FnMut(&T, &T) -> Ordering
+    {
+        (**self).sort_unstable_by(compare)
+    }
+
+    /// Rotates the slice in place so that the element at `mid` becomes the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn rotate_left(&mut self, mid: usize) {
+        (**self).rotate_left(mid)
+    }
+
+    /// Rotates the slice in place so that the element at `len - k` becomes the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k > len`.
+    pub fn rotate_right(&mut self, k: usize) {
+        (**self).rotate_right(k)
+    }
+
+    /// Reverses the order of the elements in the slice, in place.
+    pub fn reverse(&mut self) {
+        (**self).reverse()
+    }
+
+    /// Copies as many elements as fit into the front of `dst`, returning how many were copied.
+    ///
+    /// This works with any combination of variants; neither slice changes its length,
+    /// even if `dst` is owned.
+    pub fn copy_into<'b>(&self, dst: &mut ManagedSlice<'b, T>) -> usize
+        where T: Copy
+    {
+        let count = self.len().min(dst.len());
+        dst[..count].copy_from_slice(&self[..count]);
+        count
+    }
+
+    /// Reorders the slice in place so that the elements satisfying `pred` precede those that
+    /// do not, and returns the number of the former.
+    ///
+    /// The order of the elements within each group is unspecified.
+    pub fn partition_in_place<F>(&mut self, mut pred: F) -> usize
+        where F: FnMut(&T) -> bool
+    {
+        let slice = &mut **self;
+        let mut split = 0;
+        for i in 0..slice.len() {
+            if pred(&slice[i]) {
+                slice.swap(split, i);
+                split += 1;
+            }
+        }
+        split
+    }
+
+    /// Removes consecutive repeated elements, returning the number of elements kept.
+    ///
+    /// For an owned slice, this forwards to `Vec::dedup` and the slice shrinks to the
+    /// returned length. The length of a borrowed slice cannot change, so the kept elements
+    /// are moved to the front, in their original order, and the removed elements are left
+    /// after them in an unspecified order.
+    pub fn dedup(&mut self) -> usize
+        where T: PartialEq
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Removes consecutive elements that map to the same key, returning the number
+    /// of elements kept.
+    ///
+    /// See `dedup` for how the elements of a borrowed slice are rearranged.
+    pub fn dedup_by_key<K, F>(&mut self, mut key: F) -> usize
+        where K: PartialEq, F: FnMut(&mut T) -> K
+    {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    /// Removes consecutive elements for which `same_bucket(element, previous)` returns `true`,
+    /// where `previous` is the last element that was kept, returning the number
+    /// of elements kept.
+    ///
+    /// See `dedup` for how the elements of a borrowed slice are rearranged.
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F) -> usize
+        where F: FnMut(&mut T, &mut T) -> bool
+    {
+        match self {
+            &mut ManagedSlice::Borrowed(ref mut slice) => {
+                let mut len = 0;
+                for idx in 0..slice.len() {
+                    if len > 0 {
+                        let (kept, rest) = slice.split_at_mut(idx);
+                        if same_bucket(&mut rest[0], &mut kept[len - 1]) {
+                            continue
+                        }
+                    }
+                    slice.swap(len, idx);
+                    len += 1
+                }
+                len
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedSlice::Owned(ref mut vec) => {
+                vec.dedup_by(same_bucket);
+                vec.len()
+            }
+        }
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements.
+    ///
+    /// For an owned slice, this forwards to `Vec::try_reserve`. A borrowed slice cannot grow,
+    /// so this is a no-op that always succeeds. This is only available with the `std` or
+    /// `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        match self {
+            ManagedSlice::Borrowed(_) => Ok(()),
+            ManagedSlice::Owned(vec) => vec.try_reserve(additional)
+        }
+    }
+
+    /// Moves all elements of `other` into `self`, leaving `other` empty.
+    ///
+    /// This forwards to `Vec::append` if both slices are owned, and succeeds without
+    /// changing anything if `other` is empty. Otherwise, neither slice is changed, and
+    /// `AppendError::BorrowedDestination` is returned if `self` is borrowed, since it cannot
+    /// grow; or `AppendError::BorrowedSource` if `other` is borrowed, since its elements
+    /// cannot be moved out of it. See also [`append_cloned`](#method.append_cloned).
+    pub fn append<'b>(&mut self, other: &mut ManagedSlice<'b, T>) -> Result<(), AppendError> {
+        match (self, other) {
+            (_, other) if other.is_empty() => Ok(()),
+            (&mut ManagedSlice::Borrowed(_), _) => Err(AppendError::BorrowedDestination),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            (&mut ManagedSlice::Owned(ref mut vec), &mut ManagedSlice::Owned(ref mut other)) => {
+                vec.append(other);
+                Ok(())
+            }
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            (&mut ManagedSlice::Owned(_), &mut ManagedSlice::Borrowed(_)) =>
+                Err(AppendError::BorrowedSource)
+        }
+    }
+
+    /// Clones all elements of `other` onto the end of `self`, leaving `other` unchanged.
+    ///
+    /// Unlike `append`, this also works if `other` is borrowed. If `self` is borrowed and
+    /// `other` is not empty, `AppendError::BorrowedDestination` is returned, and `self`
+    /// is not changed.
+    pub fn append_cloned<'b>(&mut self, other: &ManagedSlice<'b, T>) -> Result<(), AppendError>
+        where T: Clone
+    {
+        match self {
+            _ if other.is_empty() => Ok(()),
+            &mut ManagedSlice::Borrowed(_) => Err(AppendError::BorrowedDestination),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedSlice::Owned(ref mut vec) => {
+                vec.extend_from_slice(other);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the underlying `Vec` of an owned slice, or `None` for a borrowed slice.
+    ///
+    /// This is only available with the `std` or `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn as_vec_mut(&mut self) -> Option<&mut Vec<T>> {
+        match self {
+            ManagedSlice::Borrowed(_) => None,
+            ManagedSlice::Owned(vec) => Some(vec)
+        }
+    }
+
+    /// Converts an owned slice into one with any lifetime `'b`, such as `'static`, without
+    /// cloning any elements.
+    ///
+    /// Returns the slice unchanged if it is borrowed; see also [`into_owned`](#method.into_owned).
+    /// This is only available with the `std` or `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn try_into_owned<'b>(self) -> Result<ManagedSlice<'b, T>, Self> {
+        match self {
+            borrowed @ ManagedSlice::Borrowed(_) => Err(borrowed),
+            ManagedSlice::Owned(vec) => Ok(ManagedSlice::Owned(vec))
+        }
+    }
+
+    /// Converts the slice into one with any lifetime `'b`, such as `'static`, cloning the
+    /// elements of a borrowed slice into an owned one.
+    ///
+    /// An owned slice is moved, as with [`try_into_owned`](#method.try_into_owned).
+    /// This is only available with the `std` or `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn into_owned<'b>(self) -> ManagedSlice<'b, T>
+        where T: Clone
+    {
+        self.try_into_owned().unwrap_or_else(|borrowed| ManagedSlice::Owned(borrowed.to_vec()))
+    }
+
+    /// Converts an owned slice into a boxed slice, dropping any spare capacity.
+    ///
+    /// Returns `None` for a borrowed slice. This is only available with the `std` or
+    /// `alloc` feature enabled.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn into_boxed_slice(self) -> Option<Box<[T]>> {
+        match self {
+            ManagedSlice::Borrowed(_) => None,
+            ManagedSlice::Owned(vec) => Some(vec.into_boxed_slice())
+        }
+    }
+
+    /// Removes all elements from the slice.
+    ///
+    /// For an owned slice, this forwards to `Vec::clear`, which keeps the allocated capacity.
+    /// The length of a borrowed slice cannot change, so this is a no-op that leaves its
+    /// elements in place; overwrite them with e.g. `fill` instead if needed.
+    pub fn clear(&mut self) {
+        match self {
+            &mut ManagedSlice::Borrowed(_) => (),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedSlice::Owned(ref mut vec) => vec.clear()
+        }
+    }
+
+    /// Returns the spare capacity of the slice.
+    ///
+    /// For an owned slice, this is the spare capacity of the `Vec`, which may be
+    /// written to and then made part of the slice using `set_len`. A borrowed slice
+    /// cannot grow, so it has no spare capacity and an empty slice is returned.
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        match self {
+            &mut ManagedSlice::Borrowed(_) => &mut [],
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedSlice::Owned(ref mut vec) => vec.spare_capacity_mut()
+        }
+    }
+
+    /// Forces the length of the slice to `new_len`.
+    ///
+    /// For an owned slice, this forwards to `Vec::set_len`, and always succeeds. The length
+    /// of a borrowed slice cannot change, so for a borrowed slice, `ResizeError` is returned
+    /// and nothing is changed, unless `new_len` is already its length.
+    ///
+    /// # Safety
+    ///
+    /// For an owned slice, `new_len` must be less than or equal to its capacity,
+    /// and the elements at `old_len..new_len` must be initialized.
+    pub unsafe fn set_len(&mut self, new_len: usize) -> Result<(), ResizeError> {
+        match self {
+            &mut ManagedSlice::Borrowed(ref slice) if slice.len() == new_len => Ok(()),
+            &mut ManagedSlice::Borrowed(_) => Err(ResizeError),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedSlice::Owned(ref mut vec) => {
+                vec.set_len(new_len);
+                Ok(())
+            }
+        }
+    }
+
+    /// Reinterprets a borrowed slice as a slice of another plain-old-data type.
+    ///
+    /// This is only available with the `bytemuck` feature enabled. It fails, returning
+    /// the slice unchanged, if the slice is not aligned for `U`, if its size in bytes
+    /// is not a multiple of the size of `U`, or if it is owned, since the allocation
+    /// of a `Vec` cannot be soundly reinterpreted.
+    #[cfg(feature = "bytemuck")]
+    pub fn reinterpret<U>(self) -> Result<ManagedSlice<'a, U>, Self>
+        where T: bytemuck::Pod, U: bytemuck::Pod
+    {
+        match self {
+            ManagedSlice::Borrowed(slice) => {
+                if bytemuck::try_cast_slice::<T, U>(slice).is_err() {
+                    return Err(ManagedSlice::Borrowed(slice))
+                }
+                Ok(ManagedSlice::Borrowed(bytemuck::cast_slice_mut(slice)))
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            owned @ ManagedSlice::Owned(_) => Err(owned)
+        }
+    }
+
+    /// Divides the slice into two borrowed managed slices at an index.
+    ///
+    /// The first will contain all indices from `[0, mid)` and the second will contain
+    /// all indices from `[mid, len)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at_mut(&mut self, mid: usize) -> (ManagedSlice<'_, T>, ManagedSlice<'_, T>) {
+        let (left, right) = (**self).split_at_mut(mid);
+        (ManagedSlice::Borrowed(left), ManagedSlice::Borrowed(right))
+    }
+
+    /// Splits the slice into two at an index, leaving `[0, at)` in `self` and returning
+    /// `[at, len)`.
+    ///
+    /// For an owned slice, this forwards to `Vec::split_off`, and the tail is a new owned
+    /// slice. A borrowed slice is divided with `split_at_mut`, and both parts remain
+    /// borrowed for the original lifetime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> ManagedSlice<'a, T> {
+        match self {
+            &mut ManagedSlice::Borrowed(ref mut slice) => {
+                let (head, tail) = mem::take(slice).split_at_mut(at);
+                *slice = head;
+                ManagedSlice::Borrowed(tail)
+            },
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            &mut ManagedSlice::Owned(ref mut vec) => ManagedSlice::Owned(vec.split_off(at))
+        }
+    }
+
+    /// Returns the first element and a borrowed managed slice of the rest of the elements,
+    /// or `None` if the slice is empty.
+    pub fn split_first_mut(&mut self) -> Option<(&mut T, ManagedSlice<'_, T>)> {
+        (**self).split_first_mut().map(|(first, rest)| (first, ManagedSlice::Borrowed(rest)))
+    }
+
+    /// Returns the last element and a borrowed managed slice of the rest of the elements,
+    /// or `None` if the slice is empty.
+    pub fn split_last_mut(&mut self) -> Option<(&mut T, ManagedSlice<'_, T>)> {
+        (**self).split_last_mut().map(|(last, rest)| (last, ManagedSlice::Borrowed(rest)))
+    }
+
+    /// Returns a borrowed managed slice over the elements in `range`, or `None` if it is
+    /// out of bounds.
+    pub fn get_range(&mut self, range: Range<usize>) -> Option<ManagedSlice<'_, T>> {
+        (**self).get_mut(range).map(ManagedSlice::Borrowed)
+    }
+}
+
+impl<'a, 'b: 'a, T: 'b> ManagedSlice<'a, ManagedSlice<'b, T>> {
+    /// Divides `buffer` into rows of `row_len` elements, without allocation.
+    ///
+    /// Each row is stored as a borrowed managed slice in `rows`, and a borrowed managed slice
+    /// of the rows that were stored is returned. As with `chunks_mut`, the last row is shorter
+    /// if `row_len` does not divide the length of `buffer`. If `rows` is too short to hold
+    /// every row, the remaining rows are left out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_len` is zero.
+    pub fn from_rows(buffer: &'b mut [T], row_len: usize,
+                     rows: &'a mut [ManagedSlice<'b, T>]) -> Self {
+        let mut len = 0;
+        for (row, chunk) in rows.iter_mut().zip(buffer.chunks_mut(row_len)) {
+            *row = ManagedSlice::Borrowed(chunk);
+            len += 1
+        }
+        ManagedSlice::Borrowed(&mut rows[..len])
+    }
+}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::{ManagedSlice, AppendError, ResizeError};
+    use core::convert::TryFrom;
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+    #[cfg(feature = "std")]
+    use std::format;
+    #[cfg(feature = "std")]
+    use std::collections::hash_map::DefaultHasher;
+    #[cfg(feature = "std")]
+    use core::hash::{Hash, Hasher};
+
+    #[cfg(feature = "std")]
+    fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_cmp_borrowed() {
+        let (mut a, mut b, mut c) = ([1, 2], [1, 2], [1, 3]);
+        let (a, b, c) = (ManagedSlice::Borrowed(&mut a), ManagedSlice::Borrowed(&mut b),
+                         ManagedSlice::Borrowed(&mut c));
+        assert!(a == b);
+        assert!(a != c);
+        assert!(a < c);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_cmp_hash_cross_variant() {
+        let mut data = [1, 2, 3];
+        let borrowed = ManagedSlice::Borrowed(&mut data);
+        let owned = ManagedSlice::from([1, 2, 3]);
+        let longer = ManagedSlice::from([1, 2, 3, 0]);
+        assert!(borrowed == owned);
+        assert_eq!(borrowed.cmp(&owned), core::cmp::Ordering::Equal);
+        assert!(borrowed < longer);
+        assert_eq!(hash_of(&borrowed), hash_of(&owned));
+        assert_eq!(hash_of(&borrowed), hash_of(&[1, 2, 3][..]));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_debug() {
+        let mut data = [1, 2, 3];
+        let borrowed = ManagedSlice::Borrowed(&mut data);
+        let owned = ManagedSlice::from([1, 2, 3]);
+        assert_eq!(format!("{:?}", borrowed), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", owned), format!("{:?}", borrowed));
+        assert!(format!("{:#?}", borrowed).starts_with("Borrowed("));
+        assert!(format!("{:#?}", owned).starts_with("Owned("));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_from_iter() {
+        let slice: ManagedSlice<'static, u32> = (1..4).collect();
+        assert_eq!(&*slice, &[1, 2, 3]);
+        match slice {
+            ManagedSlice::Owned(vec) => assert_eq!(vec, [1, 2, 3]),
+            _ => unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_try_from_prefix() {
+        let mut buffer = [1, 2, 3, 0, 0];
+        let slice = ManagedSlice::try_from((&mut buffer[..], 3)).unwrap();
+        assert_eq!(slice.len(), 3);
+        assert_eq!(&*slice, &[1, 2, 3]);
+
+        let slice = ManagedSlice::try_from((&mut buffer[..], 0)).unwrap();
+        assert!(slice.is_empty());
+        let slice = ManagedSlice::try_from((&mut buffer[..], 5)).unwrap();
+        assert_eq!(slice.len(), 5);
+        let (buffer, len) = ManagedSlice::try_from((&mut buffer[..], 6)).unwrap_err().into_inner();
+        assert_eq!(buffer, [1, 2, 3, 0, 0]);
+        assert_eq!(len, 6);
+    }
+
+    #[test]
+    fn test_empty() {
+        const EMPTY: ManagedSlice<'static, u8> = ManagedSlice::empty();
+        static EMPTY_STATIC: ManagedSlice<'static, u8> = ManagedSlice::empty();
+        assert_eq!(EMPTY.len(), 0);
+        assert!(EMPTY_STATIC.is_empty());
+
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::empty();
+        assert!(slice.is_empty());
+        slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.len(), 3);
+    }
+
+    #[test]
+    fn test_from_mut_array() {
+        let mut data = [1, 2, 3];
+        {
+            let mut slice = ManagedSlice::from_mut(&mut data);
+            slice[0] = 10;
+            match slice {
+                ManagedSlice::Borrowed(_) => (),
+                #[allow(unreachable_patterns)]
+                _ => panic!("expected a borrowed slice")
+            }
+        }
+        assert_eq!(data, [10, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_from_mut_vec() {
+        let mut vec = Vec::from(&[1, 2, 3][..]);
+        {
+            let mut slice = ManagedSlice::from_mut(&mut vec);
+            assert_eq!(slice.len(), 3);
+            slice[2] = 30;
+        }
+        assert_eq!(vec, [1, 2, 30]);
+    }
+
+    #[test]
+    fn test_index_borrowed() {
+        let mut data = [1, 2, 3, 4];
+        {
+            let mut slice = ManagedSlice::Borrowed(&mut data);
+            assert_eq!(slice[1], 2);
+            assert_eq!(&slice[1..3], &[2, 3]);
+            assert_eq!(&slice[..], &[1, 2, 3, 4]);
+            slice[0] = 10;
+            slice[2..].copy_from_slice(&[30, 40]);
+        }
+        assert_eq!(data, [10, 2, 30, 40]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_index_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3, 4]);
+        slice[3] = 40;
+        slice[..=1].copy_from_slice(&[10, 20]);
+        assert_eq!(slice[3], 40);
+        assert_eq!(&slice[..2], &[10, 20]);
+        assert_eq!(&slice[2..], &[3, 40]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        let mut data = [1, 2];
+        let slice = ManagedSlice::Borrowed(&mut data);
+        let _ = slice[2];
+    }
+
+    #[test]
+    fn test_iter_borrowed() {
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.iter().sum::<i32>(), 6);
+        for item in slice.iter_mut() {
+            *item *= 2;
+        }
+        assert_eq!(slice.iter().sum::<i32>(), 12);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_iter_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3]);
+        assert_eq!(slice.iter().sum::<i32>(), 6);
+        for item in slice.iter_mut() {
+            *item *= 2;
+        }
+        assert_eq!(slice.iter().sum::<i32>(), 12);
+    }
+
+    #[test]
+    fn test_enumerate_mut_borrowed() {
+        let mut data = [1, 1, 1];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        for (idx, item) in slice.enumerate_mut() {
+            *item += idx;
+        }
+        assert_eq!(data, [1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_enumerate_mut_owned() {
+        let mut slice = ManagedSlice::from([1, 1, 1]);
+        for (idx, item) in slice.enumerate_mut() {
+            *item *= idx;
+        }
+        assert_eq!(&*slice, &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_first_last_borrowed() {
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.first(), Some(&1));
+        assert_eq!(slice.last(), Some(&3));
+        *slice.first_mut().unwrap() = 10;
+        *slice.last_mut().unwrap() = 30;
+        assert_eq!(data, [10, 2, 30]);
+
+        let mut slice: ManagedSlice<u8> = ManagedSlice::Borrowed(&mut []);
+        assert_eq!(slice.first(), None);
+        assert_eq!(slice.last(), None);
+        assert_eq!(slice.first_mut(), None);
+        assert_eq!(slice.last_mut(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_first_last_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3]);
+        *slice.first_mut().unwrap() = 10;
+        *slice.last_mut().unwrap() = 30;
+        assert_eq!(slice.first(), Some(&10));
+        assert_eq!(slice.last(), Some(&30));
+
+        let mut slice: ManagedSlice<u8> = ManagedSlice::from([]);
+        assert_eq!(slice.first(), None);
+        assert_eq!(slice.last(), None);
+        assert_eq!(slice.first_mut(), None);
+        assert_eq!(slice.last_mut(), None);
+    }
+
+    #[test]
+    fn test_chunks_borrowed() {
+        let mut data = [1, 2, 3, 4, 5];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        let mut chunks = slice.chunks(2);
+        assert_eq!(chunks.next(), Some(&[1, 2][..]));
+        assert_eq!(chunks.next(), Some(&[3, 4][..]));
+        assert_eq!(chunks.next(), Some(&[5][..]));
+        assert_eq!(chunks.next(), None);
+
+        for chunk in slice.chunks_mut(2) {
+            chunk[0] = 0;
+        }
+        assert_eq!(data, [0, 2, 0, 4, 0]);
+    }
+
+    #[test]
+    fn test_windows_borrowed() {
+        let mut data = [1, 2, 3];
+        let slice = ManagedSlice::Borrowed(&mut data);
+        let mut windows = slice.windows(2);
+        assert_eq!(windows.next(), Some(&[1, 2][..]));
+        assert_eq!(windows.next(), Some(&[2, 3][..]));
+        assert_eq!(windows.next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_chunks_windows_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3, 4, 5]);
+        for chunk in slice.chunks_mut(2) {
+            chunk[0] = 0;
+        }
+        assert_eq!(&*slice, &[0, 2, 0, 4, 0]);
+        assert_eq!(slice.chunks(3).count(), 2);
+        assert_eq!(slice.windows(4).count(), 2);
+        assert_eq!(slice.windows(6).count(), 0);
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let mut buffer = [0u8; 6];
+        let mut rows = [ManagedSlice::empty(), ManagedSlice::empty(), ManagedSlice::empty()];
+        {
+            let mut image = ManagedSlice::from_rows(&mut buffer, 3, &mut rows);
+            assert_eq!(image.len(), 2);
+            for (y, row) in image.iter_mut().enumerate() {
+                assert_eq!(row.len(), 3);
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = (y * 10 + x) as u8;
+                }
+            }
+            assert_eq!(image[1][2], 12);
+        }
+        assert_eq!(buffer, [0, 1, 2, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_from_rows_short() {
+        let mut buffer = [0u8; 5];
+        let mut rows = [ManagedSlice::empty(), ManagedSlice::empty()];
+        let image = ManagedSlice::from_rows(&mut buffer, 2, &mut rows);
+        assert_eq!(image.len(), 2);
+        assert_eq!(image[0].len(), 2);
+        assert_eq!(image[1].len(), 2);
+
+        let mut buffer = [0u8; 3];
+        let mut rows = [ManagedSlice::empty(), ManagedSlice::empty()];
+        let image = ManagedSlice::from_rows(&mut buffer, 2, &mut rows);
+        assert_eq!(image[1].len(), 1);
+    }
+
+    #[test]
+    fn test_rotate_reverse_borrowed() {
+        let mut data = [1, 2, 3, 4, 5];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        slice.rotate_left(2);
+        assert_eq!(&*slice, &[3, 4, 5, 1, 2]);
+        slice.rotate_right(1);
+        assert_eq!(&*slice, &[2, 3, 4, 5, 1]);
+        slice.reverse();
+        assert_eq!(data, [1, 5, 4, 3, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_rotate_reverse_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3, 4, 5]);
+        slice.rotate_left(2);
+        assert_eq!(&*slice, &[3, 4, 5, 1, 2]);
+        slice.rotate_right(1);
+        assert_eq!(&*slice, &[2, 3, 4, 5, 1]);
+        slice.reverse();
+        assert_eq!(&*slice, &[1, 5, 4, 3, 2]);
+    }
+
+    #[test]
+    fn test_dedup_borrowed() {
+        let mut data = [1, 1, 2, 3, 3, 3, 1];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.dedup(), 4);
+        assert_eq!(&slice[..4], &[1, 2, 3, 1]);
+        assert_eq!(slice.len(), 7);
+
+        let mut data = [5, 5, 5];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.dedup(), 1);
+        assert_eq!(slice[0], 5);
+
+        let mut data: [u8; 0] = [];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.dedup(), 0);
+    }
+
+    #[test]
+    fn test_dedup_by_key_borrowed() {
+        let mut data = [10, 11, 20, 21, 22, 30];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.dedup_by_key(|x| *x / 10), 3);
+        assert_eq!(&slice[..3], &[10, 20, 30]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_dedup_owned() {
+        let mut slice = ManagedSlice::from([1, 1, 2, 3, 3, 3, 1]);
+        assert_eq!(slice.dedup(), 4);
+        assert_eq!(&*slice, &[1, 2, 3, 1]);
+
+        let mut slice = ManagedSlice::from([5, 5, 5]);
+        assert_eq!(slice.dedup(), 1);
+        assert_eq!(&*slice, &[5]);
+
+        let mut slice = ManagedSlice::from([10, 11, 20, 21, 22, 30]);
+        assert_eq!(slice.dedup_by_key(|x| *x / 10), 3);
+        assert_eq!(&*slice, &[10, 20, 30]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_into_boxed_slice() {
+        let mut vec = Vec::with_capacity(16);
+        vec.extend_from_slice(&[1, 2, 3]);
+        let slice = ManagedSlice::from(vec);
+        let boxed = slice.into_boxed_slice().unwrap();
+        assert_eq!(boxed.len(), 3);
+        assert_eq!(&*boxed, &[1, 2, 3]);
+
+        let mut data = [1, 2, 3];
+        assert!(ManagedSlice::Borrowed(&mut data).into_boxed_slice().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_from_vec_mut() {
+        let mut vec = [1, 2, 3].to_vec();
+        {
+            let mut slice = ManagedSlice::from(&mut vec);
+            match slice {
+                ManagedSlice::Borrowed(_) => (),
+                _ => unreachable!()
+            }
+            slice[0] = 10;
+        }
+        vec.push(4);
+        assert_eq!(vec, [10, 2, 3, 4]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_as_vec_mut() {
+        let mut slice = ManagedSlice::from([1, 2, 3]);
+        slice.as_vec_mut().unwrap().truncate(1);
+        assert_eq!(&*slice, &[1]);
+
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert!(slice.as_vec_mut().is_none());
+    }
+
+    #[test]
+    fn test_is_borrowed() {
+        let mut data = [1, 2, 3];
+        let slice = ManagedSlice::Borrowed(&mut data);
+        assert!(slice.is_borrowed());
+        assert!(!slice.is_owned());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_is_owned() {
+        let slice = ManagedSlice::from([1, 2, 3]);
+        assert!(slice.is_owned());
+        assert!(!slice.is_borrowed());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_try_into_owned() {
+        let slice = ManagedSlice::from([1, 2, 3]);
+        let ptr = slice.as_ptr();
+        let owned = slice.try_into_owned().unwrap();
+        assert_eq!(owned.as_ptr(), ptr);
+        assert_eq!(&*owned, &[1, 2, 3]);
+
+        let mut data = [1, 2, 3];
+        let slice = ManagedSlice::Borrowed(&mut data);
+        let slice = slice.try_into_owned().unwrap_err();
+        assert!(slice.is_borrowed());
+        assert_eq!(&*slice, &[1, 2, 3]);
+
+        let value = 1;
+        let slice = ManagedSlice::from([&value]);
+        let owned: ManagedSlice<&i32> = slice.try_into_owned().unwrap();
+        assert_eq!(*owned[0], 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_into_owned() {
+        let mut data = [1, 2, 3];
+        let owned = {
+            let slice = ManagedSlice::Borrowed(&mut data);
+            slice.into_owned()
+        };
+        data[0] = 10;
+        assert!(owned.is_owned());
+        assert_eq!(&*owned, &[1, 2, 3]);
+        assert_eq!(data, [10, 2, 3]);
+
+        let slice = ManagedSlice::from([1, 2, 3]);
+        let ptr = slice.as_ptr();
+        assert_eq!(slice.into_owned().as_ptr(), ptr);
+
+        let value = 1;
+        let mut refs = [&value];
+        let owned = ManagedSlice::Borrowed(&mut refs[..]).into_owned();
+        assert_eq!(*owned[0], 1);
+    }
+
+    #[test]
+    fn test_split_off_borrowed() {
+        let mut data = [1, 2, 3, 4];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        let mut tail = slice.split_off(4);
+        assert_eq!(&*slice, &[1, 2, 3, 4]);
+        assert!(tail.is_empty());
+        tail = slice.split_off(2);
+        assert_eq!(&*slice, &[1, 2]);
+        assert_eq!(&*tail, &[3, 4]);
+        assert!(tail.is_borrowed());
+        let tail = slice.split_off(0);
+        assert!(slice.is_empty());
+        assert_eq!(&*tail, &[1, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_off_out_of_bounds() {
+        let mut data = [1, 2];
+        let _ = ManagedSlice::Borrowed(&mut data).split_off(3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_split_off_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3, 4]);
+        let tail = slice.split_off(4);
+        assert_eq!(&*slice, &[1, 2, 3, 4]);
+        assert!(tail.is_empty());
+        let tail = slice.split_off(2);
+        assert_eq!(&*slice, &[1, 2]);
+        assert_eq!(&*tail, &[3, 4]);
+        assert!(tail.is_owned());
+        let tail = slice.split_off(0);
+        assert!(slice.is_empty());
+        assert_eq!(&*tail, &[1, 2]);
+    }
+
+    #[test]
+    fn test_into_iter_ref_borrowed() {
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        for x in &mut slice {
+            *x *= 2;
+        }
+        let mut sum = 0;
+        for x in &slice {
+            sum += *x;
+        }
+        assert_eq!(sum, 12);
+        assert_eq!(data, [2, 4, 6]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_into_iter_ref_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3]);
+        for x in &mut slice {
+            *x += 1;
+        }
+        let collected: Vec<&i32> = (&slice).into_iter().collect();
+        assert_eq!(collected, [&2, &3, &4]);
+    }
+
+    #[test]
+    fn test_copy_into_borrowed() {
+        let mut src_data = [1, 2, 3];
+        let src = ManagedSlice::Borrowed(&mut src_data);
+        let mut small_data = [0; 2];
+        let mut small = ManagedSlice::Borrowed(&mut small_data);
+        assert_eq!(src.copy_into(&mut small), 2);
+        assert_eq!(&*small, &[1, 2]);
+
+        let mut large_data = [0; 5];
+        let mut large = ManagedSlice::Borrowed(&mut large_data);
+        assert_eq!(src.copy_into(&mut large), 3);
+        assert_eq!(large_data, [1, 2, 3, 0, 0]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_copy_into_owned() {
+        let src = ManagedSlice::from([1, 2, 3]);
+        let mut dst = ManagedSlice::from([0; 5]);
+        assert_eq!(src.copy_into(&mut dst), 3);
+        assert_eq!(&*dst, &[1, 2, 3, 0, 0]);
+
+        let mut data = [0; 2];
+        let mut dst = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(src.copy_into(&mut dst), 2);
+        assert_eq!(data, [1, 2]);
+    }
+
+    #[test]
+    fn test_clear_borrowed() {
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        slice.clear();
+        assert_eq!(&*slice, &[1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_clear_owned() {
+        let mut vec = Vec::with_capacity(16);
+        vec.extend_from_slice(&[1, 2, 3]);
+        let mut slice = ManagedSlice::from(vec);
+        slice.clear();
+        assert!(slice.is_empty());
+        assert!(slice.as_vec_mut().unwrap().capacity() >= 16);
+    }
+
+    #[test]
+    fn test_partition_in_place_borrowed() {
+        let mut data = [5, 2, 8, 1, 9, 4, 7];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        let split = slice.partition_in_place(|&x| x < 5);
+        assert_eq!(split, 3);
+        assert!(slice[..split].iter().all(|&x| x < 5));
+        assert!(slice[split..].iter().all(|&x| x >= 5));
+        slice.sort_unstable();
+        assert_eq!(data, [1, 2, 4, 5, 7, 8, 9]);
+
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.partition_in_place(|_| true), 3);
+        assert_eq!(slice.partition_in_place(|_| false), 0);
+        assert_eq!(data, [1, 2, 3]);
+
+        let mut data: [i32; 0] = [];
+        assert_eq!(ManagedSlice::Borrowed(&mut data).partition_in_place(|_| true), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_partition_in_place_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3, 4, 5, 6]);
+        let split = slice.partition_in_place(|&x| x % 2 == 0);
+        assert_eq!(split, 3);
+        let mut evens = slice[..split].to_vec();
+        let mut odds = slice[split..].to_vec();
+        evens.sort();
+        odds.sort();
+        assert_eq!(evens, [2, 4, 6]);
+        assert_eq!(odds, [1, 3, 5]);
+        assert_eq!(slice.len(), 6);
+    }
+
+    #[test]
+    fn test_append_borrowed() {
+        let mut data = [1, 2];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        let mut other_data = [3, 4];
+        let mut other = ManagedSlice::Borrowed(&mut other_data);
+        assert_eq!(slice.append(&mut other), Err(AppendError::BorrowedDestination));
+        assert_eq!(slice.append_cloned(&other), Err(AppendError::BorrowedDestination));
+        assert_eq!(&*slice, &[1, 2]);
+        assert_eq!(&*other, &[3, 4]);
+
+        let mut empty = ManagedSlice::Borrowed(&mut []);
+        assert_eq!(slice.append(&mut empty), Ok(()));
+        assert_eq!(slice.append_cloned(&empty), Ok(()));
+        assert_eq!(&*slice, &[1, 2]);
+    }
+
+    #[test]
+    fn test_append_not_clone() {
+        struct NotClone;
+
+        let mut data = [NotClone];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        let mut empty = ManagedSlice::Borrowed(&mut []);
+        assert_eq!(slice.append(&mut empty), Ok(()));
+        assert_eq!(slice.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_append_owned() {
+        let mut slice = ManagedSlice::from([1, 2]);
+        let mut other = ManagedSlice::from([3, 4]);
+        assert_eq!(slice.append(&mut other), Ok(()));
+        assert_eq!(&*slice, &[1, 2, 3, 4]);
+        assert!(other.is_empty());
+        match other {
+            ManagedSlice::Owned(_) => (),
+            _ => unreachable!()
+        }
+
+        let mut other_data = [5, 6];
+        let mut other = ManagedSlice::Borrowed(&mut other_data);
+        assert_eq!(slice.append(&mut other), Err(AppendError::BorrowedSource));
+        assert_eq!(slice.len(), 4);
+        assert_eq!(slice.append_cloned(&other), Ok(()));
+        assert_eq!(&*slice, &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(&*other, &[5, 6]);
+        assert_eq!(other_data, [5, 6]);
+
+        let mut data = [1];
+        let mut borrowed = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(borrowed.append(&mut slice), Err(AppendError::BorrowedDestination));
+        assert_eq!(slice.len(), 6);
+        assert_eq!(format!("{}", AppendError::BorrowedSource), "cannot move out of a borrowed slice");
+    }
+
+    #[test]
+    fn test_split_first_last_mut_borrowed() {
+        fn sum(mut slice: ManagedSlice<i32>) -> i32 {
+            match slice.split_first_mut() {
+                Some((first, rest)) => *first + sum(rest),
+                None => 0
+            }
+        }
+
+        let mut data = [1, 2, 3];
+        assert_eq!(sum(ManagedSlice::Borrowed(&mut data)), 6);
+
+        let mut data = [1, 2, 3];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        {
+            let (last, rest) = slice.split_last_mut().unwrap();
+            *last = 30;
+            assert_eq!(&*rest, &[1, 2]);
+        }
+        assert_eq!(data, [1, 2, 30]);
+
+        let mut data = [1];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        let (first, rest) = slice.split_first_mut().unwrap();
+        assert_eq!(*first, 1);
+        assert!(rest.is_empty());
+
+        let mut data: [i32; 0] = [];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert!(slice.split_first_mut().is_none());
+        assert!(slice.split_last_mut().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_split_first_last_mut_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3]);
+        {
+            let (first, rest) = slice.split_first_mut().unwrap();
+            *first = 10;
+            assert_eq!(&*rest, &[2, 3]);
+        }
+        {
+            let (last, rest) = slice.split_last_mut().unwrap();
+            *last = 30;
+            assert_eq!(&*rest, &[10, 2]);
+        }
+        assert_eq!(&*slice, &[10, 2, 30]);
+
+        let mut slice = ManagedSlice::from([1]);
+        assert!(slice.split_last_mut().unwrap().1.is_empty());
+        let mut slice: ManagedSlice<i32> = ManagedSlice::from(Vec::new());
+        assert!(slice.split_first_mut().is_none());
+    }
+
+    #[test]
+    fn test_get_range_borrowed() {
+        let mut data = [1, 2, 3, 4];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        {
+            let mut window = slice.get_range(1..3).unwrap();
+            assert_eq!(&*window, &[2, 3]);
+            window[0] = 20;
+        }
+        assert!(slice.get_range(2..2).unwrap().is_empty());
+        assert!(slice.get_range(3..5).is_none());
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 3..1;
+        assert!(slice.get_range(reversed).is_none());
+        assert_eq!(data, [1, 20, 3, 4]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_range_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3, 4]);
+        {
+            let mut window = slice.get_range(2..4).unwrap();
+            assert_eq!(&*window, &[3, 4]);
+            window[1] = 40;
+        }
+        assert!(slice.get_range(0..5).is_none());
+        assert_eq!(&*slice, &[1, 2, 3, 40]);
+    }
+
+    #[test]
+    fn test_split_at_mut_borrowed() {
+        let mut data = [1, 2, 3, 4];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        {
+            let (mut left, mut right) = slice.split_at_mut(1);
+            assert_eq!(&*left, &[1]);
+            assert_eq!(&*right, &[2, 3, 4]);
+            left[0] = 10;
+            right[0] = 20;
+        }
+        assert_eq!(data, [10, 20, 3, 4]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_split_at_mut_owned() {
+        let mut slice = ManagedSlice::from([1, 2, 3, 4]);
+        {
+            let (mut left, mut right) = slice.split_at_mut(4);
+            assert_eq!(left.len(), 4);
+            assert!(right.is_empty());
+            left[3] = 40;
+            right.reverse();
+        }
+        assert_eq!(&*slice, &[1, 2, 3, 40]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_at_mut_out_of_bounds() {
+        let mut data = [1, 2];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        slice.split_at_mut(3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_spare_capacity_owned() {
+        let mut slice: ManagedSlice<u8> = ManagedSlice::Owned(Vec::with_capacity(4));
+        {
+            let spare = slice.spare_capacity_mut();
+            assert!(spare.len() >= 4);
+            spare[0].write(1);
+            spare[1].write(2);
+        }
+        assert_eq!(unsafe { slice.set_len(2) }, Ok(()));
+        assert_eq!(&*slice, &[1, 2]);
+    }
+
+    #[test]
+    fn test_spare_capacity_borrowed() {
+        let mut data = [1, 2];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert!(slice.spare_capacity_mut().is_empty());
+        assert_eq!(unsafe { slice.set_len(2) }, Ok(()));
+        assert_eq!(unsafe { slice.set_len(1) }, Err(ResizeError));
+        assert_eq!(unsafe { slice.set_len(3) }, Err(ResizeError));
+        assert_eq!(slice.len(), 2);
+        assert_eq!(&*slice, &[1, 2]);
+    }
+
+    #[test]
+    fn test_contains_borrowed() {
+        let mut data = [1, 2, 3];
+        let slice = ManagedSlice::Borrowed(&mut data);
+        assert!(slice.contains(&2));
+        assert!(!slice.contains(&4));
+        let slice: ManagedSlice<u8> = ManagedSlice::empty();
+        assert!(!slice.contains(&0));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_contains_owned() {
+        let slice = ManagedSlice::from(["a", "b"]);
+        assert!(slice.contains(&"b"));
+        assert!(!slice.contains(&"c"));
+    }
+
+    #[test]
+    fn test_binary_search_borrowed() {
+        let mut data = [1, 3, 5, 7];
+        let slice = ManagedSlice::Borrowed(&mut data);
+        assert_eq!(slice.binary_search(&5), Ok(2));
+        assert_eq!(slice.binary_search(&4), Err(2));
+        assert_eq!(slice.binary_search_by(|x| x.cmp(&1)), Ok(0));
+        assert_eq!(slice.binary_search_by_key(&14, |x| x * 2), Ok(3));
+        assert_eq!(slice.binary_search_by_key(&16, |x| x * 2), Err(4));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_binary_search_owned() {
+        let slice = ManagedSlice::from([(1, 'a'), (3, 'b'), (5, 'c')]);
+        assert_eq!(slice.binary_search(&(3, 'b')), Ok(1));
+        assert_eq!(slice.binary_search(&(0, 'z')), Err(0));
+        assert_eq!(slice.binary_search_by(|&(k, _)| k.cmp(&5)), Ok(2));
+        assert_eq!(slice.binary_search_by_key(&4, |&(k, _)| k), Err(2));
+    }
+
+    #[test]
+    fn test_sort_borrowed() {
+        let mut data = [3, 1, 2];
+        {
+            let mut slice = ManagedSlice::Borrowed(&mut data);
+            slice.sort_unstable();
+            assert_eq!(&*slice, &[1, 2, 3]);
+            slice.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        assert_eq!(data, [3, 2, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_sort_owned() {
+        let mut slice = ManagedSlice::from([(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')]);
+        slice.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(&*slice, &[(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+        slice.sort();
+        assert_eq!(&*slice, &[(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+        slice.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(&*slice, &[(2, 'c'), (2, 'a'), (1, 'd'), (1, 'b')]);
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[repr(align(4))]
+    struct Aligned([u8; 8]);
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn test_reinterpret() {
+        let mut data = Aligned([1, 0, 0, 0, 2, 0, 0, 0]);
+        {
+            let slice = ManagedSlice::Borrowed(&mut data.0[..]);
+            let mut words = slice.reinterpret::<u32>().unwrap();
+            assert_eq!(words.len(), 2);
+            assert_eq!(u32::from_le(words[0]), 1);
+            words[1] = 0xffff_ffff;
+        }
+        assert_eq!(&data.0[4..], &[0xff; 4]);
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn test_reinterpret_rejected() {
+        let mut data = Aligned([0; 8]);
+        let slice = ManagedSlice::Borrowed(&mut data.0[1..5]);
+        let slice = slice.reinterpret::<u32>().unwrap_err();
+        assert_eq!(slice.len(), 4);
+
+        let slice = ManagedSlice::Borrowed(&mut data.0[..6]);
+        assert!(slice.reinterpret::<u32>().is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "bytemuck", feature = "std"))]
+    fn test_reinterpret_owned() {
+        let slice = ManagedSlice::from([0u8; 4]);
+        assert!(slice.reinterpret::<u32>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_try_reserve() {
+        let mut slice: ManagedSlice<u8> = ManagedSlice::Owned(Vec::new());
+        assert!(slice.try_reserve(16).is_ok());
+        assert!(slice.spare_capacity_mut().len() >= 16);
+        assert!(slice.try_reserve(usize::MAX).is_err());
+
+        let mut data = [0u8; 2];
+        let mut slice = ManagedSlice::Borrowed(&mut data);
+        assert!(slice.try_reserve(16).is_ok());
+        assert_eq!(slice.len(), 2);
+    }
+}
@@ -4,6 +4,7 @@
 //!
 //! [`SlotMap`]: struct.SlotMap.html
 use super::{ManagedSlice as Slice};
+use error::CapacityError;
 
 /// Provides links between slots and elements.
 ///
@@ -164,14 +165,19 @@ impl<T> SlotMap<'_, T> {
 
     /// Try to insert a value into the map.
     ///
-    /// This will fail if there is not enough space. Sugar wrapper around `reserve` for inserting
-    /// values. Note that on success, an old value stored in the backing slice will be overwritten.
-    /// Use `reserve` directly if it is vital that no old value is dropped.
-    pub fn insert(&mut self, value: T) -> Option<Key> {
+    /// This will fail, returning the value in a `CapacityError`, if there is not enough space.
+    /// Sugar wrapper around `reserve` for inserting values. Note that on success, an old value
+    /// stored in the backing slice will be overwritten. Use `reserve` directly if it is vital that
+    /// no old value is dropped.
+    pub fn insert(&mut self, value: T) -> Result<Key, CapacityError<T>> {
         // Insertion must work but we don't care about the value.
-        let (index, element) = self.reserve()?;
-        *element = value;
-        Some(index)
+        match self.reserve() {
+            Some((index, element)) => {
+                *element = value;
+                Ok(index)
+            }
+            None => Err(CapacityError::new(value))
+        }
     }
 
     /// Remove an element.
@@ -305,7 +311,7 @@ impl GenerationOrFreelink {
 
 impl IndexComputer {
     pub(crate) fn from_capacity(capacity: usize) -> Self {
-        assert!(capacity < isize::max_value() as usize);
+        assert!(capacity < isize::MAX as usize);
         IndexComputer(capacity)
     }
 
@@ -369,7 +375,7 @@ impl Generation {
 
 impl Offset {
     pub(crate) fn from_int_offset(offset: usize) -> Self {
-        assert!(offset < isize::max_value() as usize);
+        assert!(offset < isize::MAX as usize);
         Offset((offset as isize).checked_neg().unwrap())
     }
 
@@ -412,7 +418,8 @@ mod tests {
         let key42 = map.insert(42).unwrap();
         let keylo = map.insert('K' as _).unwrap();
 
-        assert_eq!(map.insert(0x9999), None);
+        assert_eq!(map.insert(0x9999), Err(CapacityError::new(0x9999)));
+        assert_eq!(map.insert(0x9999).unwrap_err().into_inner(), 0x9999);
         assert_eq!(map.get(key42).cloned(), Some(42));
         assert_eq!(map.get(keylo).cloned(), Some('K' as _));
 
@@ -0,0 +1,176 @@
+//! Helpers for building packed, sorted slices of key-value pairs.
+//!
+//! A slice of `Option<(K, V)>` is *packed* if all of its `Some` entries precede all of its `None`
+//! entries, and *sorted* if the `Some` entries are in strictly ascending key order. The borrowed
+//! variant of [`ManagedMap`] upholds both properties at all times; these helpers allow building
+//! similar structures without reimplementing the underlying search and packing.
+//!
+//! [`ManagedMap`]: ../enum.ManagedMap.html
+
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+/// Like `Option`, but with `Some` values sorting first.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum RevOption<T> {
+    Some(T),
+    None
+}
+
+impl<T> From<Option<T>> for RevOption<T> {
+    fn from(other: Option<T>) -> Self {
+        match other {
+            Some(x) => RevOption::Some(x),
+            None => RevOption::None
+        }
+    }
+}
+
+impl<T> From<RevOption<T>> for Option<T> {
+    fn from(val: RevOption<T>) -> Self {
+        match val {
+            RevOption::Some(x) => Some(x),
+            RevOption::None => None
+        }
+    }
+}
+
+/// Binary searches a packed, sorted slice of pairs for a key.
+///
+/// `None` entries compare greater than any `Some` entry, so they are treated as one
+/// trailing run past the last key. If the key is found, `Ok` with its index is returned;
+/// otherwise, `Err` with the index where a pair with this key would be inserted, which
+/// is at most the number of `Some` entries.
+///
+/// The result is meaningless if the slice is not packed and sorted.
+pub fn search_packed<K, V, Q>(slice: &[Option<(K, V)>], key: &Q) -> Result<usize, usize>
+    where K: Ord + Borrow<Q>, Q: Ord + ?Sized
+{
+    search_packed_by(slice, |entry| entry.borrow().cmp(key))
+}
+
+/// Like `search_packed`, but for a slice sorted in the order given by `f`, which returns
+/// the ordering of the key of an entry relative to the key being searched for.
+///
+/// The result is meaningless if the slice is not packed and sorted in this order.
+pub fn search_packed_by<K, V, F>(slice: &[Option<(K, V)>], mut f: F) -> Result<usize, usize>
+    where F: FnMut(&K) -> Ordering
+{
+    slice.binary_search_by(|entry| {
+        match *entry {
+            Some((ref key, _)) => f(key),
+            None => Ordering::Greater
+        }
+    })
+}
+
+/// Moves all `Some` entries of a slice to its front, keeping their relative order,
+/// and returns how many there are.
+///
+/// This takes a single pass over the slice and does not allocate. Afterwards, the slice
+/// is packed, but is not sorted.
+pub fn pack_options<T>(slice: &mut [Option<T>]) -> usize {
+    let mut len = 0;
+    for idx in 0..slice.len() {
+        if slice[idx].is_some() {
+            slice.swap(len, idx);
+            len += 1
+        }
+    }
+    len
+}
+
+/// Returns whether all `Some` entries of a slice precede all of its `None` entries.
+pub fn is_packed<T>(slice: &[Option<T>]) -> bool {
+    let len = slice.iter().take_while(|item| item.is_some()).count();
+    slice[len..].iter().all(|item| item.is_none())
+}
+
+// LCOV_EXCL_START
+#[cfg(test)]
+mod test {
+    use super::{search_packed, search_packed_by, pack_options, is_packed};
+
+    #[test]
+    fn test_search_packed_found() {
+        let slice = [Some((1, 'a')), Some((3, 'b')), Some((5, 'c')), None, None];
+        assert_eq!(search_packed(&slice, &1), Ok(0));
+        assert_eq!(search_packed(&slice, &3), Ok(1));
+        assert_eq!(search_packed(&slice, &5), Ok(2));
+    }
+
+    #[test]
+    fn test_search_packed_not_found() {
+        let slice = [Some((1, 'a')), Some((3, 'b')), Some((5, 'c')), None, None];
+        assert_eq!(search_packed(&slice, &0), Err(0));
+        assert_eq!(search_packed(&slice, &2), Err(1));
+        assert_eq!(search_packed(&slice, &4), Err(2));
+        // `None` sorts after every key, so the insertion point never goes past them.
+        assert_eq!(search_packed(&slice, &6), Err(3));
+        assert_eq!(search_packed(&slice, &100), Err(3));
+    }
+
+    #[test]
+    fn test_search_packed_empty() {
+        let slice: [Option<(u8, u8)>; 0] = [];
+        assert_eq!(search_packed(&slice, &1), Err(0));
+        let slice: [Option<(u8, u8)>; 3] = [None; 3];
+        assert_eq!(search_packed(&slice, &1), Err(0));
+    }
+
+    #[test]
+    fn test_search_packed_borrow() {
+        let slice = [Some(("a", 1)), Some(("b", 2)), None];
+        assert_eq!(search_packed(&slice, "b"), Ok(1));
+        assert_eq!(search_packed(&slice, "c"), Err(2));
+    }
+
+    #[test]
+    fn test_search_packed_by() {
+        // Sorted by descending key.
+        let slice = [Some((5, 'c')), Some((3, 'b')), Some((1, 'a')), None];
+        assert_eq!(search_packed_by(&slice, |key| 3.cmp(key)), Ok(1));
+        assert_eq!(search_packed_by(&slice, |key| 4.cmp(key)), Err(1));
+        assert_eq!(search_packed_by(&slice, |key| 0.cmp(key)), Err(3));
+        assert_eq!(search_packed_by(&slice, |key| 6.cmp(key)), Err(0));
+    }
+
+    #[test]
+    fn test_pack_options() {
+        let mut slice = [None, Some(3), None, None, Some(1), Some(2), None];
+        assert!(!is_packed(&slice));
+        assert_eq!(pack_options(&mut slice), 3);
+        assert_eq!(slice, [Some(3), Some(1), Some(2), None, None, None, None]);
+        assert!(is_packed(&slice));
+        // Packing is idempotent.
+        assert_eq!(pack_options(&mut slice), 3);
+        assert_eq!(slice, [Some(3), Some(1), Some(2), None, None, None, None]);
+
+        let mut slice = [Some('a'), None, Some('b'), None, Some('c')];
+        assert_eq!(pack_options(&mut slice), 3);
+        assert_eq!(slice, [Some('a'), Some('b'), Some('c'), None, None]);
+    }
+
+    #[test]
+    fn test_pack_options_trivial() {
+        let mut slice: [Option<u8>; 0] = [];
+        assert!(is_packed(&slice));
+        assert_eq!(pack_options(&mut slice), 0);
+        let mut slice: [Option<u8>; 3] = [None; 3];
+        assert!(is_packed(&slice));
+        assert_eq!(pack_options(&mut slice), 0);
+        let mut slice = [Some(1), Some(2)];
+        assert!(is_packed(&slice));
+        assert_eq!(pack_options(&mut slice), 2);
+        assert_eq!(slice, [Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_is_packed() {
+        assert!(is_packed(&[Some(1), Some(2), None]));
+        assert!(!is_packed(&[Some(1), None, Some(2)]));
+        assert!(!is_packed(&[None, Some(1)]));
+        // Packing does not imply any order.
+        assert!(is_packed(&[Some(2), Some(1), None]));
+    }
+}